@@ -0,0 +1,95 @@
+use audio_util::kernel;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const SOURCES: usize = 8;
+const CHANNELS: usize = 2;
+const BLOCK_SIZE: usize = 64;
+
+fn signal(seed: usize, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|index| ((index * (seed + 1)) as f32 * 0.001).sin())
+        .collect()
+}
+
+/// the old way, one sample at a time with a checked lookup for every source and channel
+fn mix_per_sample(main: &mut [Vec<f32>], aux: &[Vec<Vec<f32>>], gains: &[Vec<f32>]) {
+    for sample_index in 0..main[0].len() {
+        for (channel_index, channel) in main.iter_mut().enumerate() {
+            let mut mixed = channel[sample_index] * gains[0][sample_index];
+            for (aux_index, source_gains) in gains.iter().enumerate().skip(1) {
+                let sample = aux
+                    .get(aux_index - 1)
+                    .and_then(|input| input.get(channel_index))
+                    .and_then(|input| input.get(sample_index))
+                    .copied()
+                    .unwrap_or(0.0);
+                mixed += sample * source_gains[sample_index];
+            }
+            channel[sample_index] = mixed;
+        }
+    }
+}
+
+/// the kernel fed a block at a time, the same way `buffer::mix_in_place` does it
+fn mix_blocks(main: &mut [Vec<f32>], aux: &[Vec<Vec<f32>>], gains: &[Vec<f32>]) {
+    let num_samples = main[0].len();
+    for block_start in (0..num_samples).step_by(BLOCK_SIZE) {
+        let block_end = (block_start + BLOCK_SIZE).min(num_samples);
+        let gain_slices: [&[f32]; SOURCES] =
+            std::array::from_fn(|source| &gains[source][block_start..block_end]);
+
+        for (channel_index, channel) in main.iter_mut().enumerate() {
+            let aux_slices: [&[f32]; SOURCES - 1] = std::array::from_fn(|aux_index| {
+                &aux[aux_index][channel_index][block_start..block_end]
+            });
+            kernel::mix_in_place(
+                &mut channel[block_start..block_end],
+                gain_slices[0],
+                &aux_slices,
+                &gain_slices[1..],
+            );
+        }
+    }
+}
+
+fn xyz_mixing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xyz mixing");
+
+    for buffer_size in [64, 512, 4096] {
+        let main: Vec<Vec<f32>> = (0..CHANNELS)
+            .map(|channel| signal(channel, buffer_size))
+            .collect();
+        let aux: Vec<Vec<Vec<f32>>> = (0..SOURCES - 1)
+            .map(|source| {
+                (0..CHANNELS)
+                    .map(|channel| signal(source * CHANNELS + channel, buffer_size))
+                    .collect()
+            })
+            .collect();
+        let gains: Vec<Vec<f32>> = (0..SOURCES)
+            .map(|source| signal(source + 100, buffer_size))
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("per sample", buffer_size),
+            &buffer_size,
+            |b, _| {
+                let mut main = main.clone();
+                b.iter(|| mix_per_sample(black_box(&mut main), black_box(&aux), black_box(&gains)))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("kernel", buffer_size),
+            &buffer_size,
+            |b, _| {
+                let mut main = main.clone();
+                b.iter(|| mix_blocks(black_box(&mut main), black_box(&aux), black_box(&gains)))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, xyz_mixing);
+criterion_main!(benches);
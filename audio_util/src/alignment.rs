@@ -0,0 +1,258 @@
+/// the largest offset between main and sidechain that gets measured or compensated
+pub const MAX_DELAY_MS: f32 = 10.0;
+/// how long a learn pass listens before it settles on a delay
+pub const LEARN_TIME_MS: f32 = 1000.0;
+
+/**
+delay line that can delay by fractions of a sample, using cubic hermite interpolation.
+delays below one sample use the current sample twice, which is close enough
+for lining up two recordings
+ */
+#[derive(Debug, Clone, Default)]
+pub struct FractionalDelay {
+    buffer: Vec<f32>,
+    index: usize,
+    delay: f32,
+}
+
+impl FractionalDelay {
+    /// allocates room for `max_delay` samples, so call this outside of the audio thread
+    pub fn new(max_delay: usize) -> Self {
+        Self {
+            // interpolation reads two samples past the delay
+            buffer: vec![0.0; max_delay + 3],
+            index: 0,
+            delay: 0.0,
+        }
+    }
+
+    pub fn max_delay(&self) -> f32 {
+        self.buffer.len().saturating_sub(3) as f32
+    }
+
+    pub fn set_delay(&mut self, samples: f32) {
+        self.delay = samples.clamp(0.0, self.max_delay());
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.index = 0;
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let len = self.buffer.len();
+        if len == 0 {
+            return input;
+        }
+        self.buffer[self.index] = input;
+
+        let whole = self.delay as usize;
+        let fraction = self.delay - whole as f32;
+        // `len` is added so the subtraction never goes below zero
+        let read = |age: usize| self.buffer[(self.index + len - age.min(len - 1)) % len];
+        let newer = read(whole.saturating_sub(1));
+        let current = read(whole);
+        let older = read(whole + 1);
+        let oldest = read(whole + 2);
+
+        self.index = (self.index + 1) % len;
+        hermite(newer, current, older, oldest, fraction)
+    }
+}
+
+fn hermite(newer: f32, current: f32, older: f32, oldest: f32, fraction: f32) -> f32 {
+    let c1 = 0.5 * (older - newer);
+    let c2 = newer - 2.5 * current + 2.0 * older - 0.5 * oldest;
+    let c3 = 0.5 * (oldest - newer) + 1.5 * (current - older);
+    ((c3 * fraction + c2) * fraction + c1) * fraction + current
+}
+
+/**
+finds how far the sidechain lags behind the main input by cross-correlating them.
+a positive delay means the sidechain arrives late, a negative one means the main input does.
+
+the correlation for every lag is summed up a sample at a time while learning,
+so the work is spread evenly instead of landing in one buffer
+ */
+#[derive(Debug, Clone, Default)]
+pub struct DelayEstimator {
+    max_lag: usize,
+    /// main input history, written twice so the last `2 * max_lag + 1` samples are always in one slice
+    main_history: Vec<f32>,
+    /// the sidechain is compared `max_lag` samples late so negative lags can be measured too
+    side_history: Vec<f32>,
+    history_index: usize,
+    side_index: usize,
+    correlations: Vec<f32>,
+    samples_left: usize,
+}
+
+impl DelayEstimator {
+    /// allocates room for lags up to `max_lag` samples either way
+    pub fn new(max_lag: usize) -> Self {
+        let lags = 2 * max_lag + 1;
+        Self {
+            max_lag,
+            main_history: vec![0.0; 2 * lags],
+            side_history: vec![0.0; max_lag + 1],
+            history_index: 0,
+            side_index: 0,
+            correlations: vec![0.0; lags],
+            samples_left: 0,
+        }
+    }
+
+    /// starts a new measurement that listens for `samples` samples
+    pub fn learn(&mut self, samples: usize) {
+        self.main_history.fill(0.0);
+        self.side_history.fill(0.0);
+        self.correlations.fill(0.0);
+        self.history_index = 0;
+        self.side_index = 0;
+        self.samples_left = samples.max(1);
+    }
+
+    pub fn is_learning(&self) -> bool {
+        self.samples_left > 0
+    }
+
+    /// stops a measurement without a result
+    pub fn cancel(&mut self) {
+        self.samples_left = 0;
+    }
+
+    /// feeds one sample of both inputs, returns the delay in samples once the measurement is done
+    pub fn process(&mut self, main: f32, side: f32) -> Option<f32> {
+        if !self.is_learning() {
+            return None;
+        }
+
+        let lags = self.correlations.len();
+        self.main_history[self.history_index] = main;
+        self.main_history[self.history_index + lags] = main;
+        self.history_index = (self.history_index + 1) % lags;
+        // oldest sample first, newest last
+        let window = &self.main_history[self.history_index..self.history_index + lags];
+
+        // the oldest side sample is the one `max_lag` samples ago
+        self.side_history[self.side_index] = side;
+        self.side_index = (self.side_index + 1) % self.side_history.len();
+        let late_side = self.side_history[self.side_index];
+
+        // correlation `index` belongs to lag `index - max_lag`
+        for (correlation, main) in self.correlations.iter_mut().zip(window.iter().rev()) {
+            *correlation += late_side * main;
+        }
+
+        self.samples_left -= 1;
+        match self.samples_left {
+            0 => Some(self.peak()),
+            _ => None,
+        }
+    }
+
+    /// the lag with the strongest correlation, refined between samples with a parabola
+    fn peak(&self) -> f32 {
+        let (index, _) = self.correlations.iter().enumerate().fold(
+            (self.max_lag, 0.0),
+            |best, (index, correlation)| match *correlation > best.1 {
+                true => (index, *correlation),
+                false => best,
+            },
+        );
+
+        let offset = match (index.checked_sub(1), self.correlations.get(index + 1)) {
+            (Some(before), Some(after)) => {
+                let (before, peak, after) =
+                    (self.correlations[before], self.correlations[index], *after);
+                let curvature = before - 2.0 * peak + after;
+                match curvature.abs() > f32::EPSILON {
+                    true => (0.5 * (before - after) / curvature).clamp(-0.5, 0.5),
+                    false => 0.0,
+                }
+            }
+            _ => 0.0,
+        };
+
+        index as f32 + offset - self.max_lag as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    /// white noise from a tiny lcg so the test doesn't need a rng crate
+    fn noise(len: usize) -> Vec<f32> {
+        let mut state: u32 = 12345;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    fn delayed(signal: &[f32], delay: usize) -> Vec<f32> {
+        (0..signal.len())
+            .map(|index| match index >= delay {
+                true => signal[index - delay],
+                false => 0.0,
+            })
+            .collect()
+    }
+
+    fn estimate(main: &[f32], side: &[f32]) -> f32 {
+        let mut estimator = DelayEstimator::new(32);
+        estimator.learn(main.len());
+        let mut result = None;
+        for (main, side) in main.iter().zip(side) {
+            result = estimator.process(*main, *side).or(result);
+        }
+        assert!(!estimator.is_learning());
+        result.unwrap()
+    }
+
+    #[test]
+    fn test_estimate_late_sidechain() {
+        let main = noise(4096);
+        let side = delayed(&main, 12);
+        assert_approx_eq!(f32, estimate(&main, &side), 12.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_estimate_late_main() {
+        let side = noise(4096);
+        let main = delayed(&side, 7);
+        assert_approx_eq!(f32, estimate(&main, &side), -7.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_fractional_delay() {
+        let mut delay = FractionalDelay::new(16);
+        delay.set_delay(2.5);
+        for index in 0..200 {
+            let output = delay.process((index as f32 * 0.05).sin());
+            if index > 8 {
+                let expected = ((index as f32 - 2.5) * 0.05).sin();
+                assert_approx_eq!(f32, output, expected, epsilon = 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_whole_sample_delay() {
+        let mut delay = FractionalDelay::new(16);
+        delay.set_delay(3.0);
+        let output: Vec<f32> = [1.0, 0.0, 0.0, 0.0, 0.0]
+            .iter()
+            .map(|sample| delay.process(*sample))
+            .collect();
+        assert_eq!(output, [0.0, 0.0, 0.0, 1.0, 0.0]);
+
+        // never delays past the buffer
+        delay.set_delay(100.0);
+        assert_eq!(delay.max_delay(), 16.0);
+    }
+}
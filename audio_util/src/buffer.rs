@@ -1,4 +1,5 @@
-use nih_plug::prelude::AuxiliaryBuffers;
+use crate::kernel;
+use nih_plug::prelude::{AuxiliaryBuffers, Buffer};
 
 /// checks sidechain sample at the position the main sample is
 /// just returns zero when there is an error
@@ -7,7 +8,18 @@ pub fn get_sample_at_position(
     sample_index: usize,
     aux: &mut AuxiliaryBuffers,
 ) -> f32 {
-    if let Some(buffer) = aux.inputs.first() {
+    get_source_sample_at_position(0, channel_index, sample_index, aux)
+}
+
+/// same as `get_sample_at_position` but for any aux input
+/// just returns zero when there is an error
+pub fn get_source_sample_at_position(
+    aux_index: usize,
+    channel_index: usize,
+    sample_index: usize,
+    aux: &mut AuxiliaryBuffers,
+) -> f32 {
+    if let Some(buffer) = aux.inputs.get(aux_index) {
         let buffer = buffer.as_slice_immutable();
         if let Some(sample) = buffer.get(channel_index) {
             if let Some(channel) = sample.get(sample_index) {
@@ -18,3 +30,76 @@ pub fn get_sample_at_position(
 
     0.0
 }
+
+/// the channels of the first aux input, none at all when there isn't one
+pub fn sidechain<'a>(aux: &'a AuxiliaryBuffers) -> &'a [&'a mut [f32]] {
+    aux.inputs
+        .first()
+        .map(|sidechain| sidechain.as_slice_immutable())
+        .unwrap_or_default()
+}
+
+/// the most sources `mix_in_place` can mix, enough for a four axis fader
+pub const MAX_SOURCES: usize = 16;
+/// how many samples get mixed at once, the gain curves for a block live on the stack
+pub const MAX_BLOCK_SIZE: usize = 64;
+
+static SILENCE: [f32; MAX_BLOCK_SIZE] = [0.0; MAX_BLOCK_SIZE];
+
+/**
+mixes the main input (source 0) and the aux inputs (source 1 and up) into the main buffer.
+`next_weights` is called once per sample to fill in the weight of every source for that sample,
+missing aux inputs are treated as silence.
+
+the weights are collected into gain curves and mixed a block at a time with the kernel
+ */
+pub fn mix_in_place(
+    buffer: &mut Buffer,
+    aux: &mut AuxiliaryBuffers,
+    weights: &mut [f32],
+    mut next_weights: impl FnMut(&mut [f32]),
+) {
+    let source_count = weights.len();
+    debug_assert!(source_count > 0 && source_count <= MAX_SOURCES);
+
+    let num_samples = buffer.samples();
+    let mut gains = [[0.0; MAX_BLOCK_SIZE]; MAX_SOURCES];
+
+    let mut block_start = 0;
+    while block_start < num_samples {
+        let block_len = (num_samples - block_start).min(MAX_BLOCK_SIZE);
+        let block_end = block_start + block_len;
+
+        for sample_index in 0..block_len {
+            next_weights(weights);
+            for (source_gains, weight) in gains.iter_mut().zip(weights.iter()) {
+                source_gains[sample_index] = *weight;
+            }
+        }
+        let gain_slices: [&[f32]; MAX_SOURCES] =
+            std::array::from_fn(|source| &gains[source][..block_len]);
+
+        for (channel_index, channel) in buffer.as_slice().iter_mut().enumerate() {
+            let mut aux_slices: [&[f32]; MAX_SOURCES] = [&SILENCE[..block_len]; MAX_SOURCES];
+            for (aux_index, slice) in aux_slices.iter_mut().take(source_count - 1).enumerate() {
+                if let Some(samples) = aux
+                    .inputs
+                    .get(aux_index)
+                    .and_then(|input| input.as_slice_immutable().get(channel_index))
+                    .and_then(|samples| samples.get(block_start..block_end))
+                {
+                    *slice = samples;
+                }
+            }
+
+            kernel::mix_in_place(
+                &mut channel[block_start..block_end],
+                gain_slices[0],
+                &aux_slices[..source_count - 1],
+                &gain_slices[1..source_count],
+            );
+        }
+
+        block_start = block_end;
+    }
+}
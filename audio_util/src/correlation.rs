@@ -0,0 +1,129 @@
+/// how long the running correlation takes to follow a change
+pub const DEFAULT_TIME_MS: f32 = 200.0;
+
+/**
+running pearson correlation between two signals.
+1 means they are the same signal, 0 means they have nothing in common
+and -1 means one is the other flipped upside down
+ */
+#[derive(Debug, Clone)]
+pub struct CorrelationMeter {
+    product: f32,
+    a_power: f32,
+    b_power: f32,
+    coefficient: f32,
+}
+
+impl Default for CorrelationMeter {
+    fn default() -> Self {
+        let mut meter = Self {
+            product: 0.0,
+            a_power: 0.0,
+            b_power: 0.0,
+            coefficient: 0.0,
+        };
+        meter.set_time(44100.0, DEFAULT_TIME_MS);
+        meter
+    }
+}
+
+impl CorrelationMeter {
+    /// sets how long the averages take to settle
+    pub fn set_time(&mut self, sample_rate: f32, time_ms: f32) {
+        let time_samples = (time_ms / 1000.0 * sample_rate).max(1.0);
+        self.coefficient = 1.0 - (-1.0 / time_samples).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.product = 0.0;
+        self.a_power = 0.0;
+        self.b_power = 0.0;
+    }
+
+    /// feeds one sample of each signal and returns the new correlation
+    pub fn process(&mut self, a: f32, b: f32) -> f32 {
+        self.product += (a * b - self.product) * self.coefficient;
+        self.a_power += (a * a - self.a_power) * self.coefficient;
+        self.b_power += (b * b - self.b_power) * self.coefficient;
+        self.correlation()
+    }
+
+    /**
+    feeds the summed channels of two multichannel signals through the meter
+    and writes the correlation after every sample to `correlations`.
+    `b` can have no channels at all, it is treated as silence then
+     */
+    pub fn process_channels<A: AsRef<[f32]>, B: AsRef<[f32]>>(
+        &mut self,
+        a: &[A],
+        b: &[B],
+        correlations: &mut [f32],
+    ) {
+        for (sample_index, correlation) in correlations.iter_mut().enumerate() {
+            let a_sum: f32 = a.iter().map(|channel| channel.as_ref()[sample_index]).sum();
+            let b_sum: f32 = b.iter().map(|channel| channel.as_ref()[sample_index]).sum();
+            *correlation = self.process(a_sum, b_sum);
+        }
+    }
+
+    /// the current correlation, silence counts as uncorrelated
+    pub fn correlation(&self) -> f32 {
+        let power = (self.a_power * self.b_power).sqrt();
+        match power > f32::EPSILON {
+            true => (self.product / power).clamp(-1.0, 1.0),
+            false => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    fn run(meter: &mut CorrelationMeter, b: impl Fn(f32) -> f32) -> f32 {
+        let mut correlation = 0.0;
+        for index in 0..44100 {
+            let a = (index as f32 * 0.05).sin();
+            correlation = meter.process(a, b(index as f32));
+        }
+        correlation
+    }
+
+    #[test]
+    fn test_correlation() {
+        let mut meter = CorrelationMeter::default();
+        let same = run(&mut meter, |index| (index * 0.05).sin() * 0.5);
+        assert_approx_eq!(f32, same, 1.0, epsilon = 0.001);
+
+        meter.reset();
+        let flipped = run(&mut meter, |index| -(index * 0.05).sin());
+        assert_approx_eq!(f32, flipped, -1.0, epsilon = 0.001);
+
+        meter.reset();
+        let different = run(&mut meter, |index| (index * 0.0123).sin());
+        assert!(different.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_process_channels() {
+        let mut meter = CorrelationMeter::default();
+        let left: Vec<f32> = (0..4410).map(|index| (index as f32 * 0.05).sin()).collect();
+        let right: Vec<f32> = left.iter().map(|sample| sample * 0.5).collect();
+        let mut correlations = vec![0.0; left.len()];
+
+        meter.process_channels(&[&left, &right], &[&right, &left], &mut correlations);
+        assert_approx_eq!(f32, correlations[4409], 1.0, epsilon = 0.001);
+
+        meter.reset();
+        let missing: [&[f32]; 0] = [];
+        meter.process_channels(&[&left, &right], &missing, &mut correlations);
+        assert_eq!(correlations[4409], 0.0);
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut meter = CorrelationMeter::default();
+        assert_eq!(meter.process(0.0, 0.0), 0.0);
+    }
+}
@@ -1,3 +1,7 @@
+use nih_plug::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::FRAC_PI_2;
+
 /**
 converts from a linear crossfade between 0 and 1 to
 an equal power crossfade between 0 and 1
@@ -7,6 +11,182 @@ pub fn constant_power(linear_fade_strength: f32) -> f32 {
     return f32::sqrt(linear_fade_strength);
 }
 
+/// the shape that turns a fade position into a gain for each side
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossfadeCurve {
+    /// constant gain, both gains always add up to 1
+    #[id = "linear"]
+    #[name = "Linear"]
+    Linear,
+    /// sin/cos law, the squared gains always add up to 1
+    #[id = "constant-power"]
+    #[name = "Constant Power"]
+    ConstantPower,
+    /// each side is at -3 dB in the center, this is the old `sqrt` law
+    #[id = "center-3db"]
+    #[name = "-3 dB Center"]
+    Center3Db,
+    /// each side is at -4.5 dB in the center
+    #[id = "center-4.5db"]
+    #[name = "-4.5 dB Center"]
+    Center4_5Db,
+    /// each side is at -6 dB in the center, practically linear
+    #[id = "center-6db"]
+    #[name = "-6 dB Center"]
+    Center6Db,
+    /// constant gain but with slow ends and a fast middle
+    #[id = "s-curve"]
+    #[name = "S-Curve"]
+    SCurve,
+    /// DJ style scratch curve, both sides stay at full volume
+    /// for most of the throw depending on the sharpness
+    #[id = "fast-cut"]
+    #[name = "Fast Cut"]
+    FastCut,
+}
+
+impl CrossfadeCurve {
+    /// returns the gains for the source at position 0 and the source at position 1
+    /// sharpness is only used by the fast cut curve, 1 is the same as linear
+    pub fn gains(self, position: f32, sharpness: f32) -> (f32, f32) {
+        let position = position.clamp(0.0, 1.0);
+        let inverse = 1.0 - position;
+
+        match self {
+            CrossfadeCurve::Linear => (inverse, position),
+            CrossfadeCurve::ConstantPower => {
+                let angle = position * FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+            CrossfadeCurve::Center3Db => center_law(position, -3.0),
+            CrossfadeCurve::Center4_5Db => center_law(position, -4.5),
+            CrossfadeCurve::Center6Db => center_law(position, -6.0),
+            CrossfadeCurve::SCurve => {
+                let smooth = position * position * (3.0 - 2.0 * position);
+                (1.0 - smooth, smooth)
+            }
+            CrossfadeCurve::FastCut => {
+                let sharpness = sharpness.max(1.0);
+                (
+                    (inverse * sharpness).min(1.0),
+                    (position * sharpness).min(1.0),
+                )
+            }
+        }
+    }
+}
+
+/// power law that puts both sides at `center_db` when the fader is in the middle
+fn center_law(position: f32, center_db: f32) -> (f32, f32) {
+    let exponent = util::db_to_gain(center_db).ln() / 0.5f32.ln();
+    ((1.0 - position).powf(exponent), position.powf(exponent))
+}
+
+/// a curve together with the settings that change how it behaves
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Crossfade {
+    pub curve: CrossfadeCurve,
+    /// how fast the fast cut curve reaches full volume
+    pub sharpness: f32,
+    /// hamster switch, swaps which side sits at which end of the fader
+    pub reverse: bool,
+}
+
+impl Default for Crossfade {
+    fn default() -> Self {
+        Self {
+            curve: CrossfadeCurve::Center3Db,
+            sharpness: 1.0,
+            reverse: false,
+        }
+    }
+}
+
+impl Crossfade {
+    pub fn new(curve: CrossfadeCurve) -> Self {
+        Self {
+            curve,
+            ..Default::default()
+        }
+    }
+
+    /// returns the gains for the source at position 0 and the source at position 1
+    pub fn gains(&self, position: f32) -> (f32, f32) {
+        let position = match self.reverse {
+            true => 1.0 - position,
+            false => position,
+        };
+        self.curve.gains(position, self.sharpness)
+    }
+
+    /**
+    ignores the curve and blends between the constant power law for uncorrelated sources
+    and the constant gain law for correlated ones.
+    `law` is the correlation between both sides, clamped so anything at or below 0
+    uses constant power and 1 uses constant gain
+     */
+    pub fn adaptive_gains(&self, position: f32, law: f32) -> (f32, f32) {
+        let position = match self.reverse {
+            true => 1.0 - position,
+            false => position,
+        };
+        let law = law.clamp(0.0, 1.0);
+        let (power_a, power_b) = CrossfadeCurve::ConstantPower.gains(position, 1.0);
+        let (gain_a, gain_b) = CrossfadeCurve::Linear.gains(position, 1.0);
+        (
+            power_a + (gain_a - power_a) * law,
+            power_b + (gain_b - power_b) * law,
+        )
+    }
+}
+
+/// curve settings every fader plugin shares
+/// meant to be used as a `#[nested]` param group
+#[derive(Params)]
+pub struct CrossfadeParams {
+    #[id = "curve"]
+    pub curve: EnumParam<CrossfadeCurve>,
+    #[id = "sharpness"]
+    pub sharpness: FloatParam,
+    #[id = "reverse"]
+    pub reverse: BoolParam,
+}
+
+impl Default for CrossfadeParams {
+    fn default() -> Self {
+        Self::named("")
+    }
+}
+
+impl CrossfadeParams {
+    /// puts `prefix` in front of every param name, for plugins with more than one curve
+    pub fn named(prefix: &str) -> Self {
+        Self {
+            curve: EnumParam::new(format!("{prefix}Curve"), CrossfadeCurve::Center3Db),
+            sharpness: FloatParam::new(
+                format!("{prefix}Cut Sharpness"),
+                1.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 64.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_step_size(0.01),
+            reverse: BoolParam::new(format!("{prefix}Reverse"), false),
+        }
+    }
+
+    /// reads the current param values
+    pub fn crossfade(&self) -> Crossfade {
+        Crossfade {
+            curve: self.curve.value(),
+            sharpness: self.sharpness.value(),
+            reverse: self.reverse.value(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,10 +199,94 @@ mod tests {
         // left
         assert_approx_eq!(f32, constant_power(0.0), 0.0);
         // right
-        assert_approx_eq!(f32, constant_power(1.0), 1.0); 
+        assert_approx_eq!(f32, constant_power(1.0), 1.0);
         //value too high
         assert!(approx_eq!(f32, constant_power(100.0), 1.0));
         // value too low
         assert!(approx_eq!(f32, constant_power(-100.0), 0.0))
     }
+
+    #[test]
+    fn test_curve_endpoints() {
+        for curve in [
+            CrossfadeCurve::Linear,
+            CrossfadeCurve::ConstantPower,
+            CrossfadeCurve::Center3Db,
+            CrossfadeCurve::Center4_5Db,
+            CrossfadeCurve::Center6Db,
+            CrossfadeCurve::SCurve,
+            CrossfadeCurve::FastCut,
+        ] {
+            let (a, b) = curve.gains(0.0, 4.0);
+            assert_approx_eq!(f32, a, 1.0, epsilon = 0.0001);
+            assert_approx_eq!(f32, b, 0.0, epsilon = 0.0001);
+            let (a, b) = curve.gains(1.0, 4.0);
+            assert_approx_eq!(f32, a, 0.0, epsilon = 0.0001);
+            assert_approx_eq!(f32, b, 1.0, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_center_laws() {
+        let (a, b) = CrossfadeCurve::Center3Db.gains(0.5, 1.0);
+        assert_approx_eq!(f32, util::gain_to_db(a), -3.0, epsilon = 0.001);
+        assert_approx_eq!(f32, util::gain_to_db(b), -3.0, epsilon = 0.001);
+        let (a, _) = CrossfadeCurve::Center4_5Db.gains(0.5, 1.0);
+        assert_approx_eq!(f32, util::gain_to_db(a), -4.5, epsilon = 0.001);
+        let (a, _) = CrossfadeCurve::Center6Db.gains(0.5, 1.0);
+        assert_approx_eq!(f32, util::gain_to_db(a), -6.0, epsilon = 0.001);
+        // matches the old sqrt law
+        assert_approx_eq!(f32, b, constant_power(0.5), epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_constant_power_and_gain() {
+        for step in 0..=10 {
+            let position = step as f32 / 10.0;
+            let (a, b) = CrossfadeCurve::ConstantPower.gains(position, 1.0);
+            assert_approx_eq!(f32, a * a + b * b, 1.0, epsilon = 0.0001);
+            let (a, b) = CrossfadeCurve::Linear.gains(position, 1.0);
+            assert_approx_eq!(f32, a + b, 1.0, epsilon = 0.0001);
+            let (a, b) = CrossfadeCurve::SCurve.gains(position, 1.0);
+            assert_approx_eq!(f32, a + b, 1.0, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_fast_cut() {
+        // both sides at full volume in the middle
+        let (a, b) = CrossfadeCurve::FastCut.gains(0.5, 2.0);
+        assert_approx_eq!(f32, a, 1.0);
+        assert_approx_eq!(f32, b, 1.0);
+        // sharpness of 1 is linear
+        let (a, b) = CrossfadeCurve::FastCut.gains(0.25, 1.0);
+        assert_approx_eq!(f32, a, 0.75);
+        assert_approx_eq!(f32, b, 0.25);
+    }
+
+    #[test]
+    fn test_adaptive_gains() {
+        let crossfade = Crossfade::default();
+        let (a, b) = crossfade.adaptive_gains(0.3, 0.0);
+        assert_approx_eq!(f32, a * a + b * b, 1.0, epsilon = 0.0001);
+        let (a, b) = crossfade.adaptive_gains(0.3, 1.0);
+        assert_approx_eq!(f32, a + b, 1.0, epsilon = 0.0001);
+        // negative correlation is treated like no correlation
+        assert_eq!(
+            crossfade.adaptive_gains(0.3, -0.5),
+            crossfade.adaptive_gains(0.3, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let crossfade = Crossfade {
+            curve: CrossfadeCurve::Linear,
+            sharpness: 1.0,
+            reverse: true,
+        };
+        let (a, b) = crossfade.gains(0.25);
+        assert_approx_eq!(f32, a, 0.25);
+        assert_approx_eq!(f32, b, 0.75);
+    }
 }
@@ -0,0 +1,192 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+/// number of bands the crossover splits into
+pub const BANDS: usize = 3;
+
+/// second order filter in transposed direct form 2, coefficients from the rbj cookbook
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    s1: f32,
+    s2: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BiquadKind {
+    Lowpass,
+    Highpass,
+    Allpass,
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        // passes everything through until the coefficients are set
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+}
+
+impl Biquad {
+    /// butterworth q, two of these in a row make a linkwitz-riley filter
+    fn set(&mut self, kind: BiquadKind, sample_rate: f32, frequency: f32) {
+        let omega = 2.0 * PI * (frequency / sample_rate).clamp(0.0001, 0.49);
+        let alpha = omega.sin() / (2.0 * FRAC_1_SQRT_2);
+        let cos = omega.cos();
+        let a0 = 1.0 + alpha;
+
+        let (b0, b1, b2) = match kind {
+            BiquadKind::Lowpass => ((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0),
+            BiquadKind::Highpass => ((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0),
+            BiquadKind::Allpass => (1.0 - alpha, -2.0 * cos, 1.0 + alpha),
+        };
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.s1;
+        self.s1 = self.b1 * input - self.a1 * output + self.s2;
+        self.s2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+/// 4th order linkwitz-riley filter, two butterworth biquads in a row
+#[derive(Debug, Clone, Copy, Default)]
+struct LinkwitzRiley {
+    stages: [Biquad; 2],
+}
+
+impl LinkwitzRiley {
+    fn set(&mut self, kind: BiquadKind, sample_rate: f32, frequency: f32) {
+        for stage in &mut self.stages {
+            stage.set(kind, sample_rate, frequency);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.stages.iter_mut().for_each(Biquad::reset);
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        self.stages
+            .iter_mut()
+            .fold(input, |sample, stage| stage.process(sample))
+    }
+}
+
+/**
+splits one channel into low, mid and high with 4th order linkwitz-riley filters.
+the low band goes through an allpass at the high crossover so it stays in phase
+with the other two, which means the three bands add back up to a flat allpass
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeBandCrossover {
+    low_lowpass: LinkwitzRiley,
+    low_highpass: LinkwitzRiley,
+    high_lowpass: LinkwitzRiley,
+    high_highpass: LinkwitzRiley,
+    low_allpass: Biquad,
+}
+
+impl ThreeBandCrossover {
+    /// `low` and `high` are the crossover frequencies in Hz, `high` is kept above `low`
+    pub fn set_frequencies(&mut self, sample_rate: f32, low: f32, high: f32) {
+        let high = high.max(low);
+        self.low_lowpass.set(BiquadKind::Lowpass, sample_rate, low);
+        self.low_highpass
+            .set(BiquadKind::Highpass, sample_rate, low);
+        self.high_lowpass
+            .set(BiquadKind::Lowpass, sample_rate, high);
+        self.high_highpass
+            .set(BiquadKind::Highpass, sample_rate, high);
+        // a linkwitz-riley lowpass and highpass add up to this allpass
+        self.low_allpass.set(BiquadKind::Allpass, sample_rate, high);
+    }
+
+    pub fn reset(&mut self) {
+        self.low_lowpass.reset();
+        self.low_highpass.reset();
+        self.high_lowpass.reset();
+        self.high_highpass.reset();
+        self.low_allpass.reset();
+    }
+
+    /// returns the low, mid and high band for one sample
+    pub fn process(&mut self, input: f32) -> [f32; BANDS] {
+        let low = self.low_allpass.process(self.low_lowpass.process(input));
+        let rest = self.low_highpass.process(input);
+        let mid = self.high_lowpass.process(rest);
+        let high = self.high_highpass.process(rest);
+        [low, mid, high]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn crossover() -> ThreeBandCrossover {
+        let mut crossover = ThreeBandCrossover::default();
+        crossover.set_frequencies(SAMPLE_RATE, 250.0, 2500.0);
+        crossover
+    }
+
+    #[test]
+    fn test_bands_sum_flat() {
+        for frequency in [50.0, 250.0, 1000.0, 2500.0, 10000.0] {
+            let mut crossover = crossover();
+            let mut peak: f32 = 0.0;
+            for index in 0..48000 {
+                let input = (2.0 * PI * frequency * index as f32 / SAMPLE_RATE).sin();
+                let sum: f32 = crossover.process(input).iter().sum();
+                // let the filters settle first
+                if index > 24000 {
+                    peak = peak.max(sum.abs());
+                }
+            }
+            assert_approx_eq!(f32, peak, 1.0, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn test_bands_split() {
+        // a low tone ends up in the low band
+        let mut crossover = crossover();
+        let mut peaks = [0.0f32; BANDS];
+        for index in 0..48000 {
+            let input = (2.0 * PI * 40.0 * index as f32 / SAMPLE_RATE).sin();
+            let bands = crossover.process(input);
+            if index > 24000 {
+                for (peak, band) in peaks.iter_mut().zip(bands) {
+                    *peak = peak.max(band.abs());
+                }
+            }
+        }
+        assert!(peaks[0] > 0.95);
+        assert!(peaks[1] < 0.1);
+        assert!(peaks[2] < 0.01);
+    }
+}
@@ -0,0 +1,337 @@
+use nih_plug::prelude::*;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detector {
+    #[id = "peak"]
+    #[name = "Peak"]
+    Peak,
+    #[id = "rms"]
+    #[name = "RMS"]
+    Rms,
+}
+
+/// which input's level moves the fader
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowInput {
+    #[id = "main"]
+    #[name = "Main"]
+    Main,
+    #[id = "sidechain"]
+    #[name = "Sidechain"]
+    Sidechain,
+}
+
+/// follows the level of a signal, rising at the attack time and falling at the release time
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+    detector: Detector,
+    attack: f32,
+    release: f32,
+    /// the peak level, or the mean square for rms
+    envelope: f32,
+}
+
+impl Default for EnvelopeFollower {
+    fn default() -> Self {
+        let mut follower = Self {
+            detector: Detector::Peak,
+            attack: 0.0,
+            release: 0.0,
+            envelope: 0.0,
+        };
+        follower.set_times(44100.0, 10.0, 300.0);
+        follower
+    }
+}
+
+impl EnvelopeFollower {
+    pub fn set_detector(&mut self, detector: Detector) {
+        self.detector = detector;
+    }
+
+    /// times are how long it takes to get most of the way to a new level
+    pub fn set_times(&mut self, sample_rate: f32, attack_ms: f32, release_ms: f32) {
+        let coefficient = |time_ms: f32| {
+            let time_samples = time_ms / 1000.0 * sample_rate;
+            match time_samples > 0.0 {
+                true => (-1.0 / time_samples).exp(),
+                // no time at all jumps straight to the new level
+                false => 0.0,
+            }
+        };
+        self.attack = coefficient(attack_ms);
+        self.release = coefficient(release_ms);
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+
+    /// feeds one sample and returns the level as linear gain
+    pub fn process(&mut self, input: f32) -> f32 {
+        let input = match self.detector {
+            Detector::Peak => input.abs(),
+            Detector::Rms => input * input,
+        };
+        self.follow(input)
+    }
+
+    fn follow(&mut self, input: f32) -> f32 {
+        let coefficient = match input > self.envelope {
+            true => self.attack,
+            false => self.release,
+        };
+        self.envelope = input + (self.envelope - input) * coefficient;
+        self.level()
+    }
+
+    /// the current level as linear gain
+    pub fn level(&self) -> f32 {
+        match self.detector {
+            Detector::Peak => self.envelope,
+            Detector::Rms => self.envelope.sqrt(),
+        }
+    }
+
+    /**
+    follows all channels of a signal together and writes the level after every sample to `levels`.
+    peak uses the loudest channel, rms averages the power of all of them.
+    no channels at all counts as silence
+     */
+    pub fn process_channels<A: AsRef<[f32]>>(&mut self, channels: &[A], levels: &mut [f32]) {
+        for (sample_index, level) in levels.iter_mut().enumerate() {
+            let samples = channels
+                .iter()
+                .map(|channel| channel.as_ref()[sample_index]);
+            let input = match self.detector {
+                Detector::Peak => samples.fold(0.0, |peak: f32, sample| peak.max(sample.abs())),
+                Detector::Rms => {
+                    samples.map(|sample| sample * sample).sum::<f32>()
+                        / channels.len().max(1) as f32
+                }
+            };
+            *level = self.follow(input);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AutoFade {
+    /// below this level the fader stays where it is
+    pub threshold_db: f32,
+    /// how far above the threshold the level has to go to fade all the way
+    pub range_db: f32,
+    /// fades towards the main input at 1 instead of the sidechain at 0
+    pub invert: bool,
+}
+
+impl AutoFade {
+    /// how far the level pushes the fader, from 0 to 1
+    pub fn amount(&self, level: f32) -> f32 {
+        let level_db = util::gain_to_db(level);
+        ((level_db - self.threshold_db) / self.range_db.max(f32::EPSILON)).clamp(0.0, 1.0)
+    }
+
+    /// moves `position` away from where the fader is towards one end by the level
+    pub fn apply(&self, position: f32, level: f32) -> f32 {
+        let end = match self.invert {
+            true => 1.0,
+            false => 0.0,
+        };
+        position + (end - position) * self.amount(level)
+    }
+}
+
+#[derive(Params)]
+pub struct AutoFadeParams {
+    #[id = "auto-fade"]
+    pub enabled: BoolParam,
+    #[id = "follow-input"]
+    pub input: EnumParam<FollowInput>,
+    #[id = "detector"]
+    pub detector: EnumParam<Detector>,
+    #[id = "attack"]
+    pub attack: FloatParam,
+    #[id = "release"]
+    pub release: FloatParam,
+    #[id = "threshold"]
+    pub threshold: FloatParam,
+    #[id = "range"]
+    pub range: FloatParam,
+    #[id = "invert"]
+    pub invert: BoolParam,
+}
+
+impl Default for AutoFadeParams {
+    fn default() -> Self {
+        Self {
+            enabled: BoolParam::new("Auto Fade", false),
+            input: EnumParam::new("Follow Input", FollowInput::Sidechain),
+            detector: EnumParam::new("Detector", Detector::Rms),
+            attack: time_param("Attack", 10.0, 0.1, 500.0),
+            release: time_param("Release", 300.0, 1.0, 5000.0),
+            threshold: FloatParam::new(
+                "Threshold",
+                -40.0,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            range: FloatParam::new(
+                "Range",
+                20.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 60.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            invert: BoolParam::new("Invert", false),
+        }
+    }
+}
+
+fn time_param(name: &str, default: f32, min: f32, max: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min,
+            max,
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    .with_unit(" ms")
+    .with_step_size(0.1)
+}
+
+impl AutoFadeParams {
+    /// sets the follower up from the params, call this once per buffer
+    pub fn update_follower(&self, follower: &mut EnvelopeFollower, sample_rate: f32) {
+        follower.set_detector(self.detector.value());
+        follower.set_times(sample_rate, self.attack.value(), self.release.value());
+    }
+
+    /**
+    sets the follower up from the params and follows the input they pick for the whole buffer,
+    writing the level after every sample to `levels`.
+    `sidechain` can have no channels at all, it is treated as silence then
+     */
+    pub fn follow<A: AsRef<[f32]>>(
+        &self,
+        follower: &mut EnvelopeFollower,
+        main: &[A],
+        sidechain: &[A],
+        levels: &mut [f32],
+        sample_rate: f32,
+    ) {
+        self.update_follower(follower, sample_rate);
+        let input = match self.input.value() {
+            FollowInput::Main => main,
+            FollowInput::Sidechain => sidechain,
+        };
+        follower.process_channels(input, levels);
+    }
+
+    /// reads the current param values, `None` when auto fading is off
+    pub fn auto_fade(&self) -> Option<AutoFade> {
+        match self.enabled.value() {
+            true => Some(AutoFade {
+                threshold_db: self.threshold.value(),
+                range_db: self.range.value(),
+                invert: self.invert.value(),
+            }),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_peak_follows() {
+        let mut follower = EnvelopeFollower::default();
+        follower.set_times(1000.0, 1.0, 100.0);
+        let mut level = 0.0;
+        for _ in 0..100 {
+            level = follower.process(-0.5);
+        }
+        assert_approx_eq!(f32, level, 0.5, epsilon = 0.001);
+
+        // one release time later it has fallen most of the way
+        for _ in 0..100 {
+            level = follower.process(0.0);
+        }
+        assert_approx_eq!(f32, level, 0.5 * (-1.0f32).exp(), epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_rms() {
+        let mut follower = EnvelopeFollower::default();
+        follower.set_detector(Detector::Rms);
+        follower.set_times(1000.0, 100.0, 100.0);
+        let mut level = 0.0;
+        for index in 0..10000 {
+            level = follower.process((index as f32 * 0.3).sin());
+        }
+        assert_approx_eq!(f32, level, std::f32::consts::FRAC_1_SQRT_2, epsilon = 0.02);
+    }
+
+    #[test]
+    fn test_process_channels() {
+        let mut follower = EnvelopeFollower::default();
+        follower.set_times(1000.0, 0.0, 0.0);
+        let left = [0.5, 0.0];
+        let right = [-1.0, 0.25];
+        let mut levels = [0.0; 2];
+        follower.process_channels(&[&left, &right], &mut levels);
+        assert_eq!(levels, [1.0, 0.25]);
+
+        let no_channels: [&[f32]; 0] = [];
+        follower.process_channels(&no_channels, &mut levels);
+        assert_eq!(levels, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_auto_fade() {
+        let auto_fade = AutoFade {
+            threshold_db: -40.0,
+            range_db: 20.0,
+            invert: false,
+        };
+        assert_eq!(auto_fade.amount(util::db_to_gain(-60.0)), 0.0);
+        assert_approx_eq!(
+            f32,
+            auto_fade.amount(util::db_to_gain(-30.0)),
+            0.5,
+            epsilon = 0.001
+        );
+        assert_eq!(auto_fade.amount(1.0), 1.0);
+
+        assert_approx_eq!(
+            f32,
+            auto_fade.apply(0.2, util::db_to_gain(-30.0)),
+            0.1,
+            epsilon = 0.001
+        );
+        let inverted = AutoFade {
+            invert: true,
+            ..auto_fade
+        };
+        assert_approx_eq!(
+            f32,
+            inverted.apply(0.2, util::db_to_gain(-30.0)),
+            0.6,
+            epsilon = 0.001
+        );
+        // silence leaves the fader alone
+        assert_eq!(inverted.apply(0.2, 0.0), 0.2);
+    }
+}
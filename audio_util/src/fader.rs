@@ -0,0 +1,185 @@
+use crate::{
+    correlation::{self, CorrelationMeter},
+    crossfade::Crossfade,
+    envelope::{AutoFade, AutoFadeParams, EnvelopeFollower},
+    midi::MidiControl,
+    smoothing::{AxisSmoother, SmoothingParams},
+    transition::{Grid, Transition, TransitionParams},
+};
+use nih_plug::prelude::Transport;
+
+/**
+everything that moves a single fader between the main input and the sidechain once its
+param is read: midi, smoothing, transitions, auto fading and the correlation adaptive law.
+
+call `initialize` and `reset` from the plugin's, `begin_block` and `measure` once per buffer
+and `next_weights` for every sample after feeding `midi` its events
+ */
+#[derive(Debug, Clone, Default)]
+pub struct FaderChain {
+    pub midi: MidiControl,
+    smoother: AxisSmoother,
+    transition: Transition,
+    correlation: CorrelationMeter,
+    follower: EnvelopeFollower,
+    /// the correlation for every sample of the current buffer
+    laws: Vec<f32>,
+    /// the followed level for every sample of the current buffer
+    levels: Vec<f32>,
+    /// how far into the current buffer `next_weights` is
+    sample: usize,
+    samples: usize,
+    crossfade: Crossfade,
+    adaptive: bool,
+    auto_fade: Option<AutoFade>,
+}
+
+impl FaderChain {
+    pub fn initialize(&mut self, sample_rate: f32, max_buffer_size: usize) {
+        self.correlation
+            .set_time(sample_rate, correlation::DEFAULT_TIME_MS);
+        self.laws.resize(max_buffer_size, 0.0);
+        self.levels.resize(max_buffer_size, 0.0);
+    }
+
+    /// `fader` is the unsmoothed param value, the smoother starts from there
+    pub fn reset(&mut self, fader: f32) {
+        self.correlation.reset();
+        self.transition.reset();
+        self.follower.reset();
+        self.midi.reset();
+        self.smoother.reset(fader);
+    }
+
+    /// reads the params for this buffer, `fader` is the unsmoothed param value
+    pub fn begin_block(
+        &mut self,
+        fader: f32,
+        crossfade: Crossfade,
+        transition: &TransitionParams,
+        smoothing: &SmoothingParams,
+        transport: &Transport,
+    ) {
+        self.transition.update(
+            transition.go.value(),
+            fader,
+            &transition.settings(),
+            &Grid::from_transport(transport),
+            transport.sample_rate,
+        );
+        self.midi.follow_faders(&[fader]);
+        self.smoother
+            .set_settings(&smoothing.settings(), transport.sample_rate);
+        self.crossfade = crossfade;
+    }
+
+    /**
+    measures the correlation and the followed level of the inputs for the whole buffer,
+    so this has to happen before the main input gets overwritten.
+    `sidechain` can have no channels at all, it is treated as silence then
+     */
+    pub fn measure<A: AsRef<[f32]>>(
+        &mut self,
+        adaptive: bool,
+        auto_fade: &AutoFadeParams,
+        main: &[A],
+        sidechain: &[A],
+        sample_rate: f32,
+    ) {
+        self.sample = 0;
+        self.samples = main
+            .first()
+            .map_or(0, |channel| channel.as_ref().len())
+            .min(self.laws.len());
+        self.adaptive = adaptive;
+        if adaptive {
+            self.correlation
+                .process_channels(main, sidechain, &mut self.laws[..self.samples]);
+        }
+        self.auto_fade = auto_fade.auto_fade();
+        if self.auto_fade.is_some() {
+            auto_fade.follow(
+                &mut self.follower,
+                main,
+                sidechain,
+                &mut self.levels[..self.samples],
+                sample_rate,
+            );
+        }
+    }
+
+    /// the correlation at the end of the last measured buffer, `None` when adaptive is off
+    pub fn law(&self) -> Option<f32> {
+        match self.adaptive {
+            true => self.laws[..self.samples].last().copied(),
+            false => None,
+        }
+    }
+
+    /**
+    moves one sample on from `position`, where midi and anything else left the fader.
+    fills in the weights of the main input and the sidechain and returns where the fader
+    ended up after smoothing, transitions and auto fading
+     */
+    pub fn next_weights(&mut self, position: f32, weights: &mut [f32]) -> f32 {
+        let sample = self.sample;
+        self.sample += 1;
+        let level = self.levels[..self.samples].get(sample);
+        let law = self.laws[..self.samples].get(sample);
+
+        let position = self.smoother.process(position);
+        // a running transition takes over from the fader
+        let position = self.transition.next_position().unwrap_or(position);
+        let position = match (&self.auto_fade, level) {
+            (Some(auto_fade), Some(level)) => auto_fade.apply(position, *level),
+            _ => position,
+        };
+
+        // the fader is all the way on the main input at 1
+        (weights[1], weights[0]) = match self.adaptive {
+            true => self
+                .crossfade
+                .adaptive_gains(position, law.copied().unwrap_or_default()),
+            false => self.crossfade.gains(position),
+        };
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_fader_ends() {
+        let mut chain = FaderChain::default();
+        chain.initialize(1000.0, 4);
+        let silence = [[0.0; 4]];
+        chain.measure(false, &AutoFadeParams::default(), &silence, &silence, 1000.0);
+        assert_eq!(chain.law(), None);
+
+        let mut weights = [0.0; 2];
+        assert_eq!(chain.next_weights(1.0, &mut weights), 1.0);
+        assert_approx_eq!(f32, weights[0], 1.0);
+        assert_approx_eq!(f32, weights[1], 0.0);
+
+        chain.next_weights(0.0, &mut weights);
+        assert_approx_eq!(f32, weights[0], 0.0);
+        assert_approx_eq!(f32, weights[1], 1.0);
+    }
+
+    #[test]
+    fn test_adaptive_law() {
+        let mut chain = FaderChain::default();
+        chain.initialize(1000.0, 4);
+        let signal = [[0.5, -0.5, 0.5, -0.5]];
+        chain.measure(true, &AutoFadeParams::default(), &signal, &signal, 1000.0);
+        assert_approx_eq!(f32, chain.law().unwrap(), 1.0);
+
+        // the same signal on both sides fades with constant gain
+        let mut weights = [0.0; 2];
+        chain.next_weights(0.5, &mut weights);
+        assert_approx_eq!(f32, weights[0] + weights[1], 1.0);
+    }
+}
@@ -0,0 +1,399 @@
+use crate::{midi::MAX_AXES, transition::Grid};
+use nih_plug::prelude::*;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// the longest loop in bars
+pub const MAX_BARS: usize = 16;
+/// how many recorded positions one bar holds
+pub const SLOTS_PER_BAR: usize = 128;
+const MAX_SLOTS: usize = MAX_BARS * SLOTS_PER_BAR;
+/// how many slots overdubbing keeps writing after the faders stopped moving
+const TOUCH_SLOTS: u32 = (SLOTS_PER_BAR / 8) as u32;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureMode {
+    /// the faders do what they are told, the recording stays where it is
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+    #[id = "play"]
+    #[name = "Play"]
+    Play,
+    /// replaces everything the loop passes over
+    #[id = "record"]
+    #[name = "Record"]
+    Record,
+    /// plays back, but moving a fader records over that part
+    #[id = "overdub"]
+    #[name = "Overdub"]
+    Overdub,
+}
+
+/// fader moves recorded into a loop, `None` where nothing got recorded yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gesture {
+    slots: Vec<Option<[f32; MAX_AXES]>>,
+}
+
+impl Default for Gesture {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; MAX_SLOTS],
+        }
+    }
+}
+
+impl Gesture {
+    pub fn slots(&self) -> &[Option<[f32; MAX_AXES]>] {
+        &self.slots
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureSettings {
+    pub mode: GestureMode,
+    pub bars: usize,
+}
+
+/**
+records fader moves into a loop of whole bars that follows the host transport, and plays them back.
+it only runs while the host is playing, otherwise the faders pass straight through.
+
+works on its own copy of the recording so the audio thread never waits on the plugin state.
+call `load` when the state might have changed, `update` once per buffer before `next`
+for every sample, and `publish` after the buffer to write changes back to the state
+ */
+#[derive(Debug, Clone)]
+pub struct GestureLooper {
+    slots: Vec<Option<[f32; MAX_AXES]>>,
+    /// the recording changed since it was last written to the plugin state
+    dirty: bool,
+    settings: GestureSettings,
+    /// the song position in beats, `None` while the host isn't playing
+    position: Option<f64>,
+    beats_per_sample: f64,
+    beats_per_bar: f64,
+    /// the slot the last sample was in
+    slot: usize,
+    /// the faders last sample, moving them starts overdubbing
+    last_input: [f32; MAX_AXES],
+    /// slots left to overdub
+    touch: u32,
+    /// the clear button last buffer, every flip clears
+    last_clear: Option<bool>,
+}
+
+impl Default for GestureLooper {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; MAX_SLOTS],
+            dirty: false,
+            settings: GestureSettings {
+                mode: GestureMode::Off,
+                bars: 1,
+            },
+            position: None,
+            beats_per_sample: 0.0,
+            beats_per_bar: 4.0,
+            slot: 0,
+            last_input: [0.0; MAX_AXES],
+            touch: 0,
+            last_clear: None,
+        }
+    }
+}
+
+impl GestureLooper {
+    /// copies the recording out of the plugin state
+    pub fn load(&mut self, gesture: &RwLock<Gesture>) {
+        let gesture = gesture.read();
+        for (slot, stored) in self.slots.iter_mut().enumerate() {
+            *stored = gesture.slots.get(slot).copied().flatten();
+        }
+        self.dirty = false;
+        self.touch = 0;
+    }
+
+    /// writes the recording back to the plugin state, tries again next buffer when it is in use
+    pub fn publish(&mut self, gesture: &RwLock<Gesture>) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(mut gesture) = gesture.try_write() {
+            gesture.slots.clear();
+            // the state always holds every slot, so there is room and this doesn't allocate
+            gesture.slots.extend_from_slice(&self.slots);
+            self.dirty = false;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.fill(None);
+        self.dirty = true;
+    }
+
+    /// follows the transport and clears when the clear button flipped
+    pub fn update(
+        &mut self,
+        settings: &GestureSettings,
+        clear: bool,
+        grid: &Grid,
+        sample_rate: f32,
+    ) {
+        self.settings = GestureSettings {
+            bars: settings.bars.clamp(1, MAX_BARS),
+            ..*settings
+        };
+        if self.last_clear.is_some_and(|last| last != clear) {
+            self.clear();
+        }
+        self.last_clear = Some(clear);
+
+        self.beats_per_bar = grid.beats_per_bar.max(f64::EPSILON);
+        self.position = match (grid.tempo, grid.pos_beats, grid.playing) {
+            (Some(tempo), Some(position), true) if tempo > 0.0 => {
+                self.beats_per_sample = tempo / 60.0 / sample_rate.max(1.0) as f64;
+                Some(position)
+            }
+            _ => None,
+        };
+    }
+
+    /// advances by one sample, recording `positions` or replacing them with the recording
+    pub fn next(&mut self, positions: &mut [f32]) {
+        let moved = positions
+            .iter()
+            .zip(&self.last_input)
+            .any(|(position, last)| position != last);
+        for (last, position) in self.last_input.iter_mut().zip(positions.iter()) {
+            *last = *position;
+        }
+
+        let Some(position) = self.position else {
+            return;
+        };
+        self.position = Some(position + self.beats_per_sample);
+        if self.settings.mode == GestureMode::Off {
+            return;
+        }
+
+        let slots = self.settings.bars * SLOTS_PER_BAR;
+        let loop_position =
+            (position / self.beats_per_bar * SLOTS_PER_BAR as f64).rem_euclid(slots as f64);
+        let slot = (loop_position as usize).min(slots - 1);
+        if slot != self.slot {
+            self.slot = slot;
+            self.touch = self.touch.saturating_sub(1);
+        }
+        if moved {
+            self.touch = TOUCH_SLOTS;
+        }
+
+        let record = match self.settings.mode {
+            GestureMode::Record => true,
+            GestureMode::Overdub => self.touch > 0,
+            _ => false,
+        };
+        if record {
+            let mut recorded = [0.0; MAX_AXES];
+            for (recorded, position) in recorded.iter_mut().zip(positions.iter()) {
+                *recorded = *position;
+            }
+            self.slots[slot] = Some(recorded);
+            self.dirty = true;
+            return;
+        }
+
+        // glides into the next slot when there is one
+        let Some(current) = self.slots[slot] else {
+            return;
+        };
+        let amount = loop_position.fract() as f32;
+        let next = self.slots[(slot + 1) % slots].unwrap_or(current);
+        for ((position, current), next) in positions.iter_mut().zip(current).zip(next) {
+            *position = current + (next - current) * amount;
+        }
+    }
+}
+
+#[derive(Params)]
+pub struct GestureParams {
+    #[id = "gesture-mode"]
+    pub mode: EnumParam<GestureMode>,
+    #[id = "gesture-bars"]
+    pub bars: IntParam,
+    /// every press clears the recording
+    #[id = "gesture-clear"]
+    pub clear: BoolParam,
+    #[persist = "gesture"]
+    pub gesture: RwLock<Gesture>,
+}
+
+impl Default for GestureParams {
+    fn default() -> Self {
+        Self {
+            mode: EnumParam::new("Gesture", GestureMode::Off),
+            bars: IntParam::new(
+                "Gesture Bars",
+                4,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_BARS as i32,
+                },
+            ),
+            clear: BoolParam::new("Clear Gesture", false),
+            gesture: RwLock::new(Gesture::default()),
+        }
+    }
+}
+
+impl GestureParams {
+    pub fn settings(&self) -> GestureSettings {
+        GestureSettings {
+            mode: self.mode.value(),
+            bars: self.bars.value() as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// one slot per sample at 120 bpm in 4/4
+    const SAMPLE_RATE: f32 = 64.0;
+
+    fn grid() -> Grid {
+        Grid {
+            tempo: Some(120.0),
+            pos_beats: Some(0.0),
+            bar_start_pos_beats: Some(0.0),
+            beats_per_bar: 4.0,
+            playing: true,
+        }
+    }
+
+    fn looper(mode: GestureMode) -> GestureLooper {
+        let mut looper = GestureLooper::default();
+        looper.update(
+            &GestureSettings { mode, bars: 1 },
+            false,
+            &grid(),
+            SAMPLE_RATE,
+        );
+        looper
+    }
+
+    /// runs one bar with the fader at `fader(slot)`, returns where the fader ended up
+    fn bar(
+        looper: &mut GestureLooper,
+        mode: GestureMode,
+        fader: impl Fn(usize) -> f32,
+    ) -> Vec<f32> {
+        looper.update(
+            &GestureSettings { mode, bars: 1 },
+            false,
+            &grid(),
+            SAMPLE_RATE,
+        );
+        (0..SLOTS_PER_BAR)
+            .map(|slot| {
+                let mut positions = [fader(slot)];
+                looper.next(&mut positions);
+                positions[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_record_and_play() {
+        let mut looper = looper(GestureMode::Record);
+        let ramp = |slot: usize| slot as f32 / SLOTS_PER_BAR as f32;
+        assert_eq!(
+            bar(&mut looper, GestureMode::Record, ramp),
+            (0..SLOTS_PER_BAR).map(ramp).collect::<Vec<_>>()
+        );
+
+        // the fader gets ignored while playing back
+        let played = bar(&mut looper, GestureMode::Play, |_| 0.9);
+        assert_eq!(played[10], ramp(10));
+        assert_eq!(played[100], ramp(100));
+
+        // and gets through when off
+        assert!(bar(&mut looper, GestureMode::Off, |_| 0.9)
+            .iter()
+            .all(|position| *position == 0.9));
+    }
+
+    #[test]
+    fn test_overdub_only_where_moved() {
+        let mut looper = looper(GestureMode::Record);
+        bar(&mut looper, GestureMode::Record, |_| 0.5);
+
+        // the fader moves for a few slots in the middle
+        let moving = |slot: usize| match slot {
+            0..=39 => 0.5,
+            40..=43 => slot as f32 / 100.0,
+            _ => 0.43,
+        };
+        bar(&mut looper, GestureMode::Overdub, moving);
+        let played = bar(&mut looper, GestureMode::Play, |_| 0.0);
+        assert_eq!(played[10], 0.5);
+        assert_eq!(played[41], 0.41);
+        // keeps writing a little while after the fader stopped
+        assert_eq!(played[46], 0.43);
+        assert_eq!(played[100], 0.5);
+    }
+
+    #[test]
+    fn test_stopped_host_passes_through() {
+        let mut looper = looper(GestureMode::Record);
+        bar(&mut looper, GestureMode::Record, |_| 0.5);
+        looper.update(
+            &GestureSettings {
+                mode: GestureMode::Play,
+                bars: 1,
+            },
+            false,
+            &Grid {
+                playing: false,
+                ..grid()
+            },
+            SAMPLE_RATE,
+        );
+        let mut positions = [0.2];
+        looper.next(&mut positions);
+        assert_eq!(positions, [0.2]);
+    }
+
+    #[test]
+    fn test_clear_and_state() {
+        let state = RwLock::new(Gesture::default());
+        let mut looper = looper(GestureMode::Record);
+        bar(&mut looper, GestureMode::Record, |_| 0.5);
+        looper.publish(&state);
+        assert!(!state.read().is_empty());
+
+        let mut loaded = GestureLooper::default();
+        loaded.load(&state);
+        assert_eq!(bar(&mut loaded, GestureMode::Play, |_| 0.0)[3], 0.5);
+
+        // flipping the button either way clears
+        looper.update(
+            &GestureSettings {
+                mode: GestureMode::Play,
+                bars: 1,
+            },
+            true,
+            &grid(),
+            SAMPLE_RATE,
+        );
+        looper.publish(&state);
+        assert!(state.read().is_empty());
+    }
+}
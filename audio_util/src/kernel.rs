@@ -0,0 +1,123 @@
+/**
+block mixing kernel, does
+`main[i] = main[i] * main_gains[i] + sources[0][i] * source_gains[0][i] + ...`
+for a whole block at once. every slice has to be as long as `main`.
+
+with the `simd` feature this uses `std::simd` which needs nightly,
+otherwise it uses plain slice loops that the compiler vectorises on its own
+ */
+pub fn mix_in_place(
+    main: &mut [f32],
+    main_gains: &[f32],
+    sources: &[&[f32]],
+    source_gains: &[&[f32]],
+) {
+    debug_assert_eq!(main.len(), main_gains.len());
+    debug_assert_eq!(sources.len(), source_gains.len());
+    debug_assert!(sources.iter().all(|source| source.len() == main.len()));
+    debug_assert!(source_gains.iter().all(|gains| gains.len() == main.len()));
+
+    #[cfg(feature = "simd")]
+    simd::mix_in_place(main, main_gains, sources, source_gains);
+    #[cfg(not(feature = "simd"))]
+    portable::mix_in_place(main, main_gains, sources, source_gains);
+}
+
+#[cfg(not(feature = "simd"))]
+mod portable {
+    pub fn mix_in_place(
+        main: &mut [f32],
+        main_gains: &[f32],
+        sources: &[&[f32]],
+        source_gains: &[&[f32]],
+    ) {
+        // zipping keeps the bounds checks out of the loops so they vectorise
+        for (sample, gain) in main.iter_mut().zip(main_gains) {
+            *sample *= gain;
+        }
+        for (source, gains) in sources.iter().zip(source_gains) {
+            for ((sample, source), gain) in main.iter_mut().zip(*source).zip(*gains) {
+                *sample += source * gain;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use std::simd::{f32x8, StdFloat};
+
+    const LANES: usize = 8;
+
+    pub fn mix_in_place(
+        main: &mut [f32],
+        main_gains: &[f32],
+        sources: &[&[f32]],
+        source_gains: &[&[f32]],
+    ) {
+        let vector_len = main.len() - main.len() % LANES;
+
+        for start in (0..vector_len).step_by(LANES) {
+            let end = start + LANES;
+            let mut mixed =
+                f32x8::from_slice(&main[start..end]) * f32x8::from_slice(&main_gains[start..end]);
+            for (source, gains) in sources.iter().zip(source_gains) {
+                mixed = f32x8::from_slice(&source[start..end])
+                    .mul_add(f32x8::from_slice(&gains[start..end]), mixed);
+            }
+            mixed.copy_to_slice(&mut main[start..end]);
+        }
+
+        // whatever doesn't fill a whole vector
+        for index in vector_len..main.len() {
+            let mut mixed = main[index] * main_gains[index];
+            for (source, gains) in sources.iter().zip(source_gains) {
+                mixed += source[index] * gains[index];
+            }
+            main[index] = mixed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_matches_per_sample_mixing() {
+        // odd length so the leftover samples get checked too
+        const LEN: usize = 67;
+        let signal =
+            |seed: f32| -> Vec<f32> { (0..LEN).map(|index| (index as f32 * seed).sin()).collect() };
+
+        let mut main = signal(0.1);
+        let main_gains = signal(0.01);
+        let sources = [signal(0.2), signal(0.3), signal(0.4)];
+        let source_gains = [signal(0.02), signal(0.03), signal(0.04)];
+
+        let expected: Vec<f32> = (0..LEN)
+            .map(|index| {
+                main[index] * main_gains[index]
+                    + (0..sources.len())
+                        .map(|source| sources[source][index] * source_gains[source][index])
+                        .sum::<f32>()
+            })
+            .collect();
+
+        let source_slices: Vec<&[f32]> = sources.iter().map(|source| &source[..]).collect();
+        let gain_slices: Vec<&[f32]> = source_gains.iter().map(|gains| &gains[..]).collect();
+        mix_in_place(&mut main, &main_gains, &source_slices, &gain_slices);
+
+        for (mixed, expected) in main.iter().zip(expected) {
+            assert_approx_eq!(f32, *mixed, expected, epsilon = 0.00001);
+        }
+    }
+
+    #[test]
+    fn test_no_sources() {
+        let mut main = [1.0, 2.0, 3.0];
+        mix_in_place(&mut main, &[0.5, 0.5, 0.0], &[], &[]);
+        assert_eq!(main, [0.5, 1.0, 0.0]);
+    }
+}
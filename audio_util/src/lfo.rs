@@ -0,0 +1,443 @@
+use crate::{random::Xorshift, transition::Grid};
+use nih_plug::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LfoShape {
+    #[id = "sine"]
+    #[name = "Sine"]
+    Sine,
+    #[id = "triangle"]
+    #[name = "Triangle"]
+    Triangle,
+    #[id = "saw"]
+    #[name = "Saw"]
+    Saw,
+    #[id = "square"]
+    #[name = "Square"]
+    Square,
+    /// a new random value every cycle
+    #[id = "sample-and-hold"]
+    #[name = "Sample & Hold"]
+    SampleAndHold,
+    /// glides from one random value to the next over a cycle
+    #[id = "smooth-random"]
+    #[name = "Smooth Random"]
+    SmoothRandom,
+}
+
+/// how long one cycle takes when the lfo follows the host tempo
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LfoSyncRate {
+    #[id = "4-bars"]
+    #[name = "4 Bars"]
+    FourBars,
+    #[id = "2-bars"]
+    #[name = "2 Bars"]
+    TwoBars,
+    #[id = "1-bar"]
+    #[name = "1 Bar"]
+    Bar,
+    #[id = "1/2"]
+    #[name = "1/2"]
+    Half,
+    #[id = "1/4"]
+    #[name = "1/4"]
+    Quarter,
+    #[id = "1/8"]
+    #[name = "1/8"]
+    Eighth,
+    #[id = "1/16"]
+    #[name = "1/16"]
+    Sixteenth,
+}
+
+impl LfoSyncRate {
+    /// the length of one cycle in quarter notes
+    pub fn beats(self, beats_per_bar: f64) -> f64 {
+        match self {
+            LfoSyncRate::FourBars => beats_per_bar * 4.0,
+            LfoSyncRate::TwoBars => beats_per_bar * 2.0,
+            LfoSyncRate::Bar => beats_per_bar,
+            LfoSyncRate::Half => 2.0,
+            LfoSyncRate::Quarter => 1.0,
+            LfoSyncRate::Eighth => 0.5,
+            LfoSyncRate::Sixteenth => 0.25,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LfoSettings {
+    pub shape: LfoShape,
+    pub rate_hz: f32,
+    pub sync: bool,
+    pub sync_rate: LfoSyncRate,
+    /// 1 swings across the whole axis
+    pub depth: f32,
+    /// added to the axis position
+    pub offset: f32,
+    /// in cycles, so 0.25 is a quarter cycle ahead
+    pub phase_offset: f32,
+}
+
+/**
+low frequency oscillator that moves a fade axis around the position the axis param is at.
+two of them on the same rate with a quarter cycle between them trace a circle,
+different rates trace lissajous orbits
+ */
+#[derive(Debug, Clone)]
+pub struct Lfo {
+    /// 0 to 1, one full cycle
+    phase: f64,
+    /// how far the phase moves every sample
+    increment: f64,
+    random: Xorshift,
+    /// the random value at the start and the end of the current cycle
+    random_from: f32,
+    random_to: f32,
+}
+
+impl Lfo {
+    /// lfos with different seeds get different random shapes
+    pub fn new(seed: u32) -> Self {
+        let mut lfo = Self {
+            phase: 0.0,
+            increment: 0.0,
+            random: Xorshift::new(seed),
+            random_from: 0.0,
+            random_to: 0.0,
+        };
+        lfo.random_from = lfo.random.next_bipolar();
+        lfo.random_to = lfo.random.next_bipolar();
+        lfo
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    /**
+    call once per buffer. works out the speed for this buffer and,
+    when synced to a playing host, snaps the phase to the song position
+     */
+    pub fn begin_block(&mut self, settings: &LfoSettings, grid: &Grid, sample_rate: f32) {
+        let sample_rate = sample_rate.max(1.0) as f64;
+        let tempo = grid.tempo.filter(|tempo| *tempo > 0.0);
+
+        match (settings.sync, tempo) {
+            (true, Some(tempo)) => {
+                let beats = settings
+                    .sync_rate
+                    .beats(grid.beats_per_bar)
+                    .max(f64::EPSILON);
+                self.increment = tempo / 60.0 / beats / sample_rate;
+                if let (Some(position), true) = (grid.pos_beats, grid.playing) {
+                    self.set_phase((position / beats).rem_euclid(1.0));
+                }
+            }
+            // without a tempo a synced lfo keeps going at the free rate
+            _ => self.increment = settings.rate_hz as f64 / sample_rate,
+        }
+    }
+
+    /**
+    the host position drifts a little against the lfo's own count, so only a jump of more than
+    half a cycle counts as wrapping. the random shapes would jump mid cycle otherwise
+     */
+    fn set_phase(&mut self, phase: f64) {
+        let moved = phase - self.phase;
+        if moved < -0.5 {
+            // the host wrapped before the lfo did
+            self.next_cycle();
+            self.phase = phase;
+        } else if moved > 0.5 {
+            // the lfo wrapped just before the host did, so it stays in the cycle it started
+            self.phase = phase - 1.0;
+        } else {
+            self.phase = phase;
+        }
+    }
+
+    fn next_cycle(&mut self) {
+        self.random_from = self.random_to;
+        self.random_to = self.random.next_bipolar();
+    }
+
+    /// the wave at the current phase, from -1 to 1
+    fn value(&self, shape: LfoShape, phase_offset: f32) -> f32 {
+        let phase = (self.phase as f32 + phase_offset).rem_euclid(1.0);
+        match shape {
+            LfoShape::Sine => (phase * TAU).sin(),
+            LfoShape::Triangle => 4.0 * ((phase + 0.75).rem_euclid(1.0) - 0.5).abs() - 1.0,
+            LfoShape::Saw => phase * 2.0 - 1.0,
+            LfoShape::Square => match phase < 0.5 {
+                true => 1.0,
+                false => -1.0,
+            },
+            LfoShape::SampleAndHold => self.random_from,
+            LfoShape::SmoothRandom => {
+                // the phase offset doesn't apply, the random values belong to whole cycles
+                let amount = 0.5 - 0.5 * (self.phase.max(0.0) as f32 * PI).cos();
+                self.random_from + (self.random_to - self.random_from) * amount
+            }
+        }
+    }
+
+    /// advances by one sample and moves `position` by the lfo, the result stays between 0 and 1
+    pub fn next(&mut self, position: f32, settings: &LfoSettings) -> f32 {
+        let value = self.value(settings.shape, settings.phase_offset);
+
+        self.phase += self.increment;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.next_cycle();
+        }
+
+        (position + settings.offset + settings.depth * 0.5 * value).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Params)]
+pub struct LfoParams {
+    #[id = "shape"]
+    pub shape: EnumParam<LfoShape>,
+    #[id = "rate"]
+    pub rate: FloatParam,
+    #[id = "sync"]
+    pub sync: BoolParam,
+    #[id = "sync-rate"]
+    pub sync_rate: EnumParam<LfoSyncRate>,
+    #[id = "depth"]
+    pub depth: FloatParam,
+    #[id = "offset"]
+    pub offset: FloatParam,
+    #[id = "phase"]
+    pub phase: FloatParam,
+}
+
+impl LfoParams {
+    /// puts `prefix` in front of every param name, there is usually one of these per axis
+    pub fn named(prefix: &str) -> Self {
+        Self {
+            shape: EnumParam::new(format!("{prefix}LFO Shape"), LfoShape::Sine),
+            rate: FloatParam::new(
+                format!("{prefix}LFO Rate"),
+                0.5,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(0.01),
+            sync: BoolParam::new(format!("{prefix}LFO Tempo Sync"), false),
+            sync_rate: EnumParam::new(format!("{prefix}LFO Sync Rate"), LfoSyncRate::Bar),
+            // no movement until the depth is turned up
+            depth: FloatParam::new(
+                format!("{prefix}LFO Depth"),
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            offset: FloatParam::new(
+                format!("{prefix}LFO Offset"),
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_step_size(0.001),
+            phase: FloatParam::new(
+                format!("{prefix}LFO Phase"),
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 360.0,
+                },
+            )
+            .with_unit("°")
+            .with_step_size(1.0),
+        }
+    }
+
+    /// reads the current param values
+    pub fn settings(&self) -> LfoSettings {
+        LfoSettings {
+            shape: self.shape.value(),
+            rate_hz: self.rate.value(),
+            sync: self.sync.value(),
+            sync_rate: self.sync_rate.value(),
+            depth: self.depth.value(),
+            offset: self.offset.value(),
+            phase_offset: self.phase.value() / 360.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    /// a power of two keeps the phase steps exact
+    const SAMPLE_RATE: f32 = 1024.0;
+
+    fn settings(shape: LfoShape) -> LfoSettings {
+        LfoSettings {
+            shape,
+            rate_hz: 1.0,
+            sync: false,
+            sync_rate: LfoSyncRate::Quarter,
+            depth: 1.0,
+            offset: 0.0,
+            phase_offset: 0.0,
+        }
+    }
+
+    /// one second at 1 Hz, so exactly one cycle
+    fn cycle(lfo: &mut Lfo, settings: &LfoSettings) -> Vec<f32> {
+        lfo.begin_block(settings, &Grid::default(), SAMPLE_RATE);
+        (0..1024).map(|_| lfo.next(0.5, settings)).collect()
+    }
+
+    #[test]
+    fn test_shapes() {
+        let mut lfo = Lfo::new(1);
+        let sine = cycle(&mut lfo, &settings(LfoShape::Sine));
+        assert_approx_eq!(f32, sine[0], 0.5);
+        assert_approx_eq!(f32, sine[256], 1.0);
+        assert_approx_eq!(f32, sine[768], 0.0, epsilon = 0.0001);
+
+        let triangle = cycle(&mut Lfo::new(1), &settings(LfoShape::Triangle));
+        assert_approx_eq!(f32, triangle[0], 0.5);
+        assert_approx_eq!(f32, triangle[256], 1.0);
+        assert_approx_eq!(f32, triangle[512], 0.5);
+        assert_approx_eq!(f32, triangle[768], 0.0);
+
+        let saw = cycle(&mut Lfo::new(1), &settings(LfoShape::Saw));
+        assert_approx_eq!(f32, saw[0], 0.0);
+        assert_approx_eq!(f32, saw[512], 0.5);
+
+        let square = cycle(&mut Lfo::new(1), &settings(LfoShape::Square));
+        assert_eq!(square[100], 1.0);
+        assert_eq!(square[600], 0.0);
+    }
+
+    #[test]
+    fn test_circle() {
+        // a quarter cycle apart, so x and y stay on a circle around the center
+        let x = cycle(&mut Lfo::new(1), &settings(LfoShape::Sine));
+        let y_settings = LfoSettings {
+            phase_offset: 0.25,
+            ..settings(LfoShape::Sine)
+        };
+        let y = cycle(&mut Lfo::new(1), &y_settings);
+        for (x, y) in x.iter().zip(&y) {
+            let radius = ((x - 0.5).powi(2) + (y - 0.5).powi(2)).sqrt();
+            assert_approx_eq!(f32, radius, 0.5, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_random_shapes() {
+        let settings = settings(LfoShape::SampleAndHold);
+        let mut lfo = Lfo::new(7);
+        let first = cycle(&mut lfo, &settings);
+        let second = cycle(&mut lfo, &settings);
+        // holds for a whole cycle and then jumps
+        assert!(first.iter().all(|value| *value == first[0]));
+        assert_ne!(first[0], second[0]);
+
+        // smooth random starts where the last cycle ended
+        let settings = LfoSettings {
+            shape: LfoShape::SmoothRandom,
+            ..settings
+        };
+        let mut lfo = Lfo::new(7);
+        let first = cycle(&mut lfo, &settings);
+        let second = cycle(&mut lfo, &settings);
+        assert_approx_eq!(f32, first[1023], second[0], epsilon = 0.001);
+
+        // the same seed gives the same values
+        assert_eq!(cycle(&mut Lfo::new(7), &settings), first);
+    }
+
+    #[test]
+    fn test_tempo_sync() {
+        let settings = LfoSettings {
+            sync: true,
+            ..settings(LfoShape::Saw)
+        };
+        let grid = Grid {
+            tempo: Some(120.0),
+            pos_beats: Some(10.5),
+            bar_start_pos_beats: Some(8.0),
+            beats_per_bar: 4.0,
+            playing: true,
+        };
+        let mut lfo = Lfo::new(1);
+        lfo.begin_block(&settings, &grid, SAMPLE_RATE);
+        // halfway through the quarter note
+        assert_approx_eq!(f32, lfo.next(0.5, &settings), 0.5);
+        // a quarter note at 120 bpm is 512 samples
+        for _ in 0..255 {
+            lfo.next(0.5, &settings);
+        }
+        assert_approx_eq!(f32, lfo.next(0.5, &settings), 0.0, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_sync_drift_keeps_random_values() {
+        let settings = LfoSettings {
+            sync: true,
+            ..settings(LfoShape::SampleAndHold)
+        };
+        let grid = |pos_beats| Grid {
+            tempo: Some(120.0),
+            pos_beats: Some(pos_beats),
+            bar_start_pos_beats: Some(8.0),
+            beats_per_bar: 4.0,
+            playing: true,
+        };
+        let mut lfo = Lfo::new(3);
+        lfo.begin_block(&settings, &grid(10.5), SAMPLE_RATE);
+        let held = lfo.next(0.5, &settings);
+        // the host is a hair behind where the lfo got to
+        lfo.begin_block(&settings, &grid(10.5), SAMPLE_RATE);
+        assert_eq!(lfo.next(0.5, &settings), held);
+
+        // the lfo wraps a sample before the host does
+        lfo.begin_block(&settings, &grid(10.999), SAMPLE_RATE);
+        lfo.next(0.5, &settings);
+        let next = lfo.next(0.5, &settings);
+        assert_ne!(next, held);
+        lfo.begin_block(&settings, &grid(10.9995), SAMPLE_RATE);
+        assert_eq!(lfo.next(0.5, &settings), next);
+        lfo.begin_block(&settings, &grid(11.01), SAMPLE_RATE);
+        assert_eq!(lfo.next(0.5, &settings), next);
+    }
+
+    #[test]
+    fn test_depth_and_offset() {
+        let settings = LfoSettings {
+            depth: 0.0,
+            offset: 0.25,
+            ..settings(LfoShape::Sine)
+        };
+        let values = cycle(&mut Lfo::new(1), &settings);
+        assert!(values.iter().all(|value| *value == 0.75));
+
+        let settings = LfoSettings {
+            offset: 0.5,
+            depth: 1.0,
+            ..settings
+        };
+        let values = cycle(&mut Lfo::new(1), &settings);
+        assert!(values.iter().all(|value| *value <= 1.0));
+    }
+}
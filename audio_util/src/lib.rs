@@ -1,2 +1,23 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+pub mod alignment;
 pub mod buffer;
+pub mod correlation;
 pub mod crossfade;
+pub mod crossover;
+pub mod envelope;
+pub mod fader;
+pub mod gesture;
+pub mod kernel;
+pub mod lfo;
+pub mod midi;
+pub mod multilinear;
+pub mod puck;
+pub mod random;
+pub mod scene;
+pub mod smoothing;
+pub mod spectral;
+pub mod trajectory;
+pub mod transition;
+pub mod vector;
+pub mod wander;
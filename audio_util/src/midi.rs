@@ -0,0 +1,461 @@
+use nih_plug::prelude::*;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// the most axes midi can move, x, y and z
+pub const MAX_AXES: usize = 3;
+/// the fine half of a 14 bit pair is sent on the coarse cc number plus this
+const LSB_OFFSET: u8 = 32;
+/// the largest value a 14 bit pair can send
+const MAX_14_BIT: f32 = 16383.0;
+
+/// the positions stored on every note, `None` for notes that nothing was stored on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotePositions {
+    notes: Vec<Option<[f32; MAX_AXES]>>,
+}
+
+impl Default for NotePositions {
+    fn default() -> Self {
+        Self {
+            notes: vec![None; 128],
+        }
+    }
+}
+
+impl NotePositions {
+    pub fn get(&self, note: u8) -> Option<[f32; MAX_AXES]> {
+        self.notes.get(note as usize).copied().flatten()
+    }
+
+    pub fn set(&mut self, note: u8, positions: [f32; MAX_AXES]) {
+        if let Some(slot) = self.notes.get_mut(note as usize) {
+            *slot = Some(positions);
+        }
+    }
+
+    pub fn clear(&mut self, note: u8) {
+        if let Some(slot) = self.notes.get_mut(note as usize) {
+            *slot = None;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiSettings {
+    /// the cc that moves every axis, the coarse half when using 14 bit pairs
+    pub ccs: [Option<u8>; MAX_AXES],
+    /// pairs every cc below 32 with the one 32 above it for finer steps
+    pub fourteen_bit: bool,
+    /// how long recalling a note takes to get to its positions, 0 jumps
+    pub glide_samples: f32,
+    /// notes store the current positions instead of recalling them
+    pub store: bool,
+}
+
+/**
+moves the fader axes from midi. a cc or a recalled note takes an axis over from its param,
+and moving the param hands it back.
+
+`load` the note positions in `initialize`, call `follow_faders` once per buffer,
+feed events with `handle_events`, call `next` for every sample and `publish` at the end
+of the buffer
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MidiControl {
+    /// where midi put every axis, `None` when the param is in control
+    positions: [Option<f32>; MAX_AXES],
+    /// the param values of the current buffer
+    faders: [f32; MAX_AXES],
+    /// the param values when midi took over, a different one means the param moved
+    held_faders: [f32; MAX_AXES],
+    /// where every axis was last sample, this is what gets stored on a note
+    current: [f32; MAX_AXES],
+    /// the last coarse value of every 14 bit pair
+    msb: [u8; MAX_AXES],
+    glide_steps: [f32; MAX_AXES],
+    glide_targets: [f32; MAX_AXES],
+    glide_left: [u32; MAX_AXES],
+    /// the last program change that nothing picked up yet
+    program: Option<u8>,
+    notes: NotePositions,
+    /// a note position was stored since they were last written to the plugin state
+    dirty: bool,
+}
+
+impl MidiControl {
+    /// lets go of every axis, the note positions stay
+    pub fn reset(&mut self) {
+        self.positions = [None; MAX_AXES];
+        self.held_faders = [0.0; MAX_AXES];
+        self.msb = [0; MAX_AXES];
+        self.glide_steps = [0.0; MAX_AXES];
+        self.glide_targets = [0.0; MAX_AXES];
+        self.glide_left = [0; MAX_AXES];
+        self.program = None;
+    }
+
+    /// copies the note positions out of the plugin state
+    pub fn load(&mut self, notes: &RwLock<NotePositions>) {
+        self.notes.clone_from(&notes.read());
+        self.dirty = false;
+    }
+
+    /// writes the note positions back to the plugin state, tries again next buffer when it is in use
+    pub fn publish(&mut self, notes: &RwLock<NotePositions>) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(mut notes) = notes.try_write() {
+            // both always hold every note, so this doesn't allocate
+            notes.clone_from(&self.notes);
+            self.dirty = false;
+        }
+    }
+
+    /// hands every axis whose param moved since midi took over back to the param
+    pub fn follow_faders(&mut self, faders: &[f32]) {
+        for (axis, fader) in faders.iter().enumerate().take(MAX_AXES) {
+            if self.positions[axis].is_some() && *fader != self.held_faders[axis] {
+                self.positions[axis] = None;
+                self.glide_left[axis] = 0;
+            }
+            self.faders[axis] = *fader;
+        }
+    }
+
+    /// where every axis was last sample
+    pub fn positions(&self) -> [f32; MAX_AXES] {
+        self.current
+    }
+
+    /// hands out the last program change since the last call
+    pub fn take_program(&mut self) -> Option<u8> {
+        self.program.take()
+    }
+
+    /// whether midi moves the axis right now
+    pub fn is_active(&self, axis: usize) -> bool {
+        self.positions.get(axis).is_some_and(Option::is_some)
+    }
+
+    /// handles every event up to and including `sample`, fetching more with `next_event`
+    pub fn handle_events(
+        &mut self,
+        sample: u32,
+        event: &mut Option<NoteEvent<()>>,
+        mut next_event: impl FnMut() -> Option<NoteEvent<()>>,
+        settings: &MidiSettings,
+    ) {
+        while let Some(current) = *event {
+            if current.timing() > sample {
+                break;
+            }
+            self.handle_event(&current, settings);
+            *event = next_event();
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &NoteEvent<()>, settings: &MidiSettings) {
+        match *event {
+            NoteEvent::MidiCC { cc, value, .. } => self.control_change(cc, value, settings),
+            NoteEvent::NoteOn { note, .. } => match settings.store {
+                true => {
+                    self.notes.set(note, self.current);
+                    self.dirty = true;
+                }
+                false => {
+                    if let Some(positions) = self.notes.get(note) {
+                        self.recall(positions, settings.glide_samples);
+                    }
+                }
+            },
+            NoteEvent::MidiProgramChange { program, .. } => self.program = Some(program),
+            _ => (),
+        }
+    }
+
+    /// `value` is the normalised cc value nih-plug hands out
+    fn control_change(&mut self, cc: u8, value: f32, settings: &MidiSettings) {
+        let value = (value * 127.0).round().clamp(0.0, 127.0) as u8;
+        for axis in 0..MAX_AXES {
+            let Some(msb_cc) = settings.ccs[axis] else {
+                continue;
+            };
+            let paired = settings.fourteen_bit && msb_cc < LSB_OFFSET;
+            if cc == msb_cc {
+                self.msb[axis] = value;
+                let position = match paired {
+                    // the fine half follows right after and fills in the rest
+                    true => (value as u16 * 128) as f32 / MAX_14_BIT,
+                    false => value as f32 / 127.0,
+                };
+                self.take(axis, position);
+            } else if paired && cc == msb_cc + LSB_OFFSET {
+                let position = (self.msb[axis] as u16 * 128 + value as u16) as f32 / MAX_14_BIT;
+                self.take(axis, position);
+            }
+        }
+    }
+
+    fn take(&mut self, axis: usize, position: f32) {
+        self.positions[axis] = Some(position);
+        self.held_faders[axis] = self.faders[axis];
+        self.glide_left[axis] = 0;
+    }
+
+    /// takes every axis over and moves it to `positions` over `glide_samples`
+    pub fn recall(&mut self, positions: [f32; MAX_AXES], glide_samples: f32) {
+        let glide = glide_samples.round() as u32;
+        for (axis, target) in positions.into_iter().enumerate() {
+            self.take(axis, self.current[axis]);
+            match glide {
+                0 => self.positions[axis] = Some(target),
+                _ => {
+                    self.glide_steps[axis] = (target - self.current[axis]) / glide as f32;
+                    self.glide_targets[axis] = target;
+                    self.glide_left[axis] = glide;
+                }
+            }
+        }
+    }
+
+    /// replaces the param positions of this sample for every axis midi controls
+    pub fn next(&mut self, positions: &mut [f32]) {
+        for (axis, position) in positions.iter_mut().enumerate().take(MAX_AXES) {
+            if let Some(midi) = &mut self.positions[axis] {
+                if self.glide_left[axis] > 0 {
+                    self.glide_left[axis] -= 1;
+                    *midi = match self.glide_left[axis] {
+                        0 => self.glide_targets[axis],
+                        _ => *midi + self.glide_steps[axis],
+                    };
+                }
+                *position = *midi;
+            }
+            self.current[axis] = *position;
+        }
+    }
+}
+
+#[derive(Params)]
+pub struct MidiParams {
+    #[id = "x-cc"]
+    pub x_cc: IntParam,
+    #[id = "y-cc"]
+    pub y_cc: IntParam,
+    #[id = "z-cc"]
+    pub z_cc: IntParam,
+    #[id = "14-bit-cc"]
+    pub fourteen_bit: BoolParam,
+    #[id = "note-glide"]
+    pub glide: FloatParam,
+    #[id = "store-notes"]
+    pub store: BoolParam,
+    #[persist = "note-positions"]
+    pub notes: RwLock<NotePositions>,
+}
+
+impl MidiParams {
+    /**
+    one cc for every name in `axes`, named after it. the ccs for axes past that
+    are hidden and turned off
+     */
+    pub fn new(axes: &[&str]) -> Self {
+        // general purpose controllers 1 to 3
+        let cc = |axis: usize, default_name: &str| {
+            let name = axes.get(axis).copied().unwrap_or(default_name);
+            let param = IntParam::new(
+                format!("{name} CC"),
+                match axis < axes.len() {
+                    true => 16 + axis as i32,
+                    false => -1,
+                },
+                IntRange::Linear { min: -1, max: 127 },
+            )
+            .with_value_to_string(Arc::new(|value| match value < 0 {
+                true => String::from("Off"),
+                false => value.to_string(),
+            }))
+            .with_string_to_value(Arc::new(|string| match string.trim() {
+                "Off" | "off" => Some(-1),
+                string => string.parse().ok(),
+            }))
+            .non_automatable();
+            match axis < axes.len() {
+                true => param,
+                false => param.hide(),
+            }
+        };
+
+        Self {
+            x_cc: cc(0, "X"),
+            y_cc: cc(1, "Y"),
+            z_cc: cc(2, "Z"),
+            fourteen_bit: BoolParam::new("14-bit CCs", false).non_automatable(),
+            glide: FloatParam::new(
+                "Note Glide",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 10000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+            store: BoolParam::new("Store Positions On Notes", false),
+            notes: RwLock::new(NotePositions::default()),
+        }
+    }
+
+    pub fn settings(&self, sample_rate: f32) -> MidiSettings {
+        let cc = |param: &IntParam| u8::try_from(param.value()).ok();
+        MidiSettings {
+            ccs: [cc(&self.x_cc), cc(&self.y_cc), cc(&self.z_cc)],
+            fourteen_bit: self.fourteen_bit.value(),
+            glide_samples: self.glide.value() / 1000.0 * sample_rate,
+            store: self.store.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    const SETTINGS: MidiSettings = MidiSettings {
+        ccs: [Some(16), Some(17), None],
+        fourteen_bit: false,
+        glide_samples: 0.0,
+        store: false,
+    };
+
+    fn cc(cc: u8, value: u8) -> NoteEvent<()> {
+        NoteEvent::MidiCC {
+            timing: 0,
+            channel: 0,
+            cc,
+            value: value as f32 / 127.0,
+        }
+    }
+
+    fn note_on(note: u8) -> NoteEvent<()> {
+        NoteEvent::NoteOn {
+            timing: 0,
+            voice_id: None,
+            channel: 0,
+            note,
+            velocity: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_cc_takes_over_until_fader_moves() {
+        let mut midi = MidiControl::default();
+        midi.follow_faders(&[0.5, 0.5]);
+        midi.handle_event(&cc(16, 127), &SETTINGS);
+
+        let mut positions = [0.5, 0.5];
+        midi.next(&mut positions);
+        assert_eq!(positions, [1.0, 0.5]);
+
+        // the same fader value keeps midi in control
+        midi.follow_faders(&[0.5, 0.5]);
+        let mut positions = [0.5, 0.5];
+        midi.next(&mut positions);
+        assert_eq!(positions, [1.0, 0.5]);
+
+        midi.follow_faders(&[0.25, 0.5]);
+        let mut positions = [0.25, 0.5];
+        midi.next(&mut positions);
+        assert_eq!(positions, [0.25, 0.5]);
+    }
+
+    #[test]
+    fn test_14_bit_pairs() {
+        let settings = MidiSettings {
+            fourteen_bit: true,
+            ..SETTINGS
+        };
+        let mut midi = MidiControl::default();
+        midi.handle_event(&cc(16, 64), &settings);
+        midi.handle_event(&cc(48, 1), &settings);
+
+        let mut positions = [0.0; 2];
+        midi.next(&mut positions);
+        assert_approx_eq!(f32, positions[0], 8193.0 / 16383.0);
+
+        midi.handle_event(&cc(16, 127), &settings);
+        midi.handle_event(&cc(48, 127), &settings);
+        midi.next(&mut positions);
+        assert_eq!(positions[0], 1.0);
+    }
+
+    #[test]
+    fn test_notes_store_and_glide() {
+        let mut midi = MidiControl::default();
+        let mut positions = [0.2, 0.8];
+        midi.next(&mut positions);
+
+        let store = MidiSettings {
+            store: true,
+            ..SETTINGS
+        };
+        midi.handle_event(&note_on(60), &store);
+        let notes = RwLock::new(NotePositions::default());
+        midi.publish(&notes);
+        assert_eq!(notes.read().get(60), Some([0.2, 0.8, 0.0]));
+        assert_eq!(notes.read().get(61), None);
+
+        let mut positions = [0.6, 0.4];
+        midi.next(&mut positions);
+        // resetting keeps what was stored
+        midi.reset();
+
+        let glide = MidiSettings {
+            glide_samples: 4.0,
+            ..SETTINGS
+        };
+        midi.handle_event(&note_on(60), &glide);
+        for expected in [0.5, 0.4, 0.3, 0.2] {
+            let mut positions = [0.6, 0.4];
+            midi.next(&mut positions);
+            assert_approx_eq!(f32, positions[0], expected, epsilon = 0.0001);
+        }
+
+        // notes without positions do nothing
+        midi.handle_event(&note_on(61), &SETTINGS);
+        let mut positions = [0.6, 0.4];
+        midi.next(&mut positions);
+        assert_approx_eq!(f32, positions[1], 0.8, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_handle_events_until_sample() {
+        let mut midi = MidiControl::default();
+        let mut queue = vec![
+            NoteEvent::MidiCC {
+                timing: 3,
+                channel: 0,
+                cc: 16,
+                value: 0.0,
+            },
+            cc(16, 127),
+        ];
+        let mut event = queue.pop();
+
+        midi.handle_events(0, &mut event, || queue.pop(), &SETTINGS);
+        let mut positions = [0.5, 0.5];
+        midi.next(&mut positions);
+        assert_eq!(positions[0], 1.0);
+        assert!(event.is_some());
+
+        midi.handle_events(3, &mut event, || queue.pop(), &SETTINGS);
+        midi.next(&mut positions);
+        assert_eq!(positions[0], 0.0);
+        assert!(event.is_none());
+    }
+}
@@ -0,0 +1,249 @@
+use crate::crossfade::Crossfade;
+use nih_plug::prelude::*;
+
+/// the most axes `next_corner_weights` can handle
+pub const MAX_AXES: usize = 8;
+
+/// how many sources a fader with this many axes needs
+pub const fn source_count(axes: usize) -> usize {
+    1 << axes
+}
+
+/**
+fills `weights` with the gain of every corner of the fade space.
+corner `i` sits where axis `d` is high if bit `d` of `i` is set,
+so the corners of a three axis fader go [0, 0, 0], [1, 0, 0], [0, 1, 0], [1, 1, 0], [0, 0, 1]...
+
+every axis goes through the crossfade curve, so with a linear curve these are the
+plain multilinear weights that add up to 1 and with a constant power curve
+the squared weights add up to 1 and the power is preserved
+ */
+pub fn corner_weights(positions: &[f32], crossfade: &Crossfade, weights: &mut [f32]) {
+    debug_assert_eq!(weights.len(), source_count(positions.len()));
+
+    weights.fill(1.0);
+    for (axis, position) in positions.iter().enumerate() {
+        let (low, high) = crossfade.gains(*position);
+        for (corner, weight) in weights.iter_mut().enumerate() {
+            *weight *= match corner & (1 << axis) {
+                0 => low,
+                _ => high,
+            };
+        }
+    }
+}
+
+/**
+the corner weights for blending from one curve to another, `amount` goes from 0 at `from`
+to 1 at `to`. blending the weights instead of the curve settings keeps the gains smooth
+even when the curve type changes
+ */
+pub fn morphed_corner_weights(
+    positions: &[f32],
+    from: &Crossfade,
+    to: &Crossfade,
+    amount: f32,
+    weights: &mut [f32],
+) {
+    corner_weights(positions, from, weights);
+    if amount <= 0.0 || from == to {
+        return;
+    }
+
+    let mut to_weights = [0.0; source_count(MAX_AXES)];
+    let to_weights = &mut to_weights[..weights.len()];
+    corner_weights(positions, to, to_weights);
+    for (weight, to_weight) in weights.iter_mut().zip(to_weights.iter()) {
+        *weight += (to_weight - *weight) * amount.min(1.0);
+    }
+}
+
+/// what the corner weights get scaled to so the loudness stays the same everywhere
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalisation {
+    /// use the weights as they come out of the curves
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+    /// squared weights add up to 1, right for uncorrelated sources
+    #[id = "power"]
+    #[name = "Constant Power"]
+    Power,
+    /// weights add up to 1, right for correlated sources
+    #[id = "amplitude"]
+    #[name = "Constant Gain"]
+    Amplitude,
+}
+
+/// scales the weights so their power or their sum is 1, all zero weights are left alone
+pub fn normalise(weights: &mut [f32], normalisation: Normalisation) {
+    let total = match normalisation {
+        Normalisation::Off => return,
+        Normalisation::Power => weights
+            .iter()
+            .map(|weight| weight * weight)
+            .sum::<f32>()
+            .sqrt(),
+        Normalisation::Amplitude => weights.iter().sum(),
+    };
+
+    if total > 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= total);
+    }
+}
+
+/**
+reads the next smoothed position of every axis and fills in the normalised corner weights.
+call this once per sample so automation lands on the sample it was meant for
+ */
+pub fn next_corner_weights(
+    smoothers: &[&Smoother<f32>],
+    crossfade: &Crossfade,
+    normalisation: Normalisation,
+    weights: &mut [f32],
+) {
+    debug_assert!(smoothers.len() <= MAX_AXES);
+
+    let mut positions = [0.0; MAX_AXES];
+    for (position, smoother) in positions.iter_mut().zip(smoothers) {
+        *position = smoother.next();
+    }
+    corner_weights(&positions[..smoothers.len()], crossfade, weights);
+    normalise(weights, normalisation);
+}
+
+/// mixes one sample of every source together using the corner weights
+pub fn mix(weights: &[f32], samples: &[f32]) -> f32 {
+    weights
+        .iter()
+        .zip(samples)
+        .map(|(weight, sample)| weight * sample)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossfade::CrossfadeCurve;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_corners() {
+        let crossfade = Crossfade::new(CrossfadeCurve::Linear);
+        let mut weights = [0.0; 8];
+        corner_weights(&[1.0, 0.0, 1.0], &crossfade, &mut weights);
+        assert_eq!(weights, [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_multilinear_weights_sum_to_one() {
+        let crossfade = Crossfade::new(CrossfadeCurve::Linear);
+        let mut weights = [0.0; 16];
+        corner_weights(&[0.1, 0.7, 0.4, 0.95], &crossfade, &mut weights);
+        assert_approx_eq!(f32, weights.iter().sum(), 1.0, epsilon = 0.0001);
+
+        // matches plain bilinear interpolation
+        let mut weights = [0.0; 4];
+        corner_weights(&[0.25, 0.5], &crossfade, &mut weights);
+        assert_approx_eq!(f32, weights[0], 0.75 * 0.5);
+        assert_approx_eq!(f32, weights[1], 0.25 * 0.5);
+        assert_approx_eq!(f32, weights[3], 0.25 * 0.5);
+    }
+
+    #[test]
+    fn test_power_preserving_weights() {
+        let crossfade = Crossfade::new(CrossfadeCurve::ConstantPower);
+        let mut weights = [0.0; 8];
+        corner_weights(&[0.3, 0.5, 0.8], &crossfade, &mut weights);
+        let power: f32 = weights.iter().map(|weight| weight * weight).sum();
+        assert_approx_eq!(f32, power, 1.0, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_normalise() {
+        // a linear curve dips in power in the middle of the plane
+        let crossfade = Crossfade::new(CrossfadeCurve::Linear);
+        let mut weights = [0.0; 4];
+        corner_weights(&[0.5, 0.5], &crossfade, &mut weights);
+        let power: f32 = weights.iter().map(|weight| weight * weight).sum();
+        assert_approx_eq!(f32, power, 0.25);
+
+        normalise(&mut weights, Normalisation::Power);
+        let power: f32 = weights.iter().map(|weight| weight * weight).sum();
+        assert_approx_eq!(f32, power, 1.0, epsilon = 0.0001);
+
+        // and the center bumps up in amplitude with a constant power curve
+        let crossfade = Crossfade::new(CrossfadeCurve::ConstantPower);
+        let mut weights = [0.0; 8];
+        corner_weights(&[0.5, 0.2, 0.9], &crossfade, &mut weights);
+        normalise(&mut weights, Normalisation::Amplitude);
+        assert_approx_eq!(f32, weights.iter().sum(), 1.0, epsilon = 0.0001);
+
+        let mut weights = [0.0; 4];
+        normalise(&mut weights, Normalisation::Power);
+        assert_eq!(weights, [0.0; 4]);
+    }
+
+    #[test]
+    fn test_mid_block_automation() {
+        const SAMPLE_RATE: f32 = 48000.0;
+        const BLOCK_SIZE: usize = 128;
+        const AUTOMATION_SAMPLE: usize = 40;
+        // 1 ms at 48 kHz
+        const SMOOTHING_STEPS: usize = 48;
+
+        let crossfade = Crossfade::new(CrossfadeCurve::Linear);
+        let x = Smoother::new(SmoothingStyle::Linear(1.0));
+        let y = Smoother::new(SmoothingStyle::Linear(1.0));
+        x.reset(0.0);
+        y.reset(0.0);
+
+        // the plugin wrapper splits the block where the automation happens
+        // and sets the new target before the second half gets processed
+        let mut block = [[0.0; 4]; BLOCK_SIZE];
+        for (sample_index, weights) in block.iter_mut().enumerate() {
+            if sample_index == AUTOMATION_SAMPLE {
+                x.set_target(SAMPLE_RATE, 1.0);
+            }
+            next_corner_weights(&[&x, &y], &crossfade, Normalisation::Off, weights);
+        }
+
+        // nothing moves before the automation point
+        for weights in &block[..AUTOMATION_SAMPLE] {
+            assert_eq!(*weights, [1.0, 0.0, 0.0, 0.0]);
+        }
+        // the first sample after it already starts moving
+        assert!(block[AUTOMATION_SAMPLE][1] > 0.0);
+        assert!(block[AUTOMATION_SAMPLE][1] < block[AUTOMATION_SAMPLE + 1][1]);
+        // and the target is reached exactly once the smoother is done
+        let last_smoothed = AUTOMATION_SAMPLE + SMOOTHING_STEPS - 1;
+        assert!(block[last_smoothed - 1][1] < 1.0);
+        for weights in &block[last_smoothed..] {
+            assert_eq!(*weights, [0.0, 1.0, 0.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn test_morphed_weights() {
+        let linear = Crossfade::new(CrossfadeCurve::Linear);
+        let power = Crossfade::new(CrossfadeCurve::ConstantPower);
+        let mut weights = [0.0; 2];
+
+        morphed_corner_weights(&[0.5], &linear, &power, 0.0, &mut weights);
+        assert_eq!(weights, [0.5, 0.5]);
+        morphed_corner_weights(&[0.5], &linear, &power, 1.0, &mut weights);
+        assert_approx_eq!(f32, weights[0], std::f32::consts::FRAC_1_SQRT_2);
+        morphed_corner_weights(&[0.5], &linear, &power, 0.5, &mut weights);
+        assert_approx_eq!(
+            f32,
+            weights[1],
+            (0.5 + std::f32::consts::FRAC_1_SQRT_2) / 2.0,
+            epsilon = 0.0001
+        );
+    }
+
+    #[test]
+    fn test_mix() {
+        assert_approx_eq!(f32, mix(&[0.5, 0.25, 0.25], &[1.0, -1.0, 2.0]), 0.75);
+    }
+}
@@ -0,0 +1,335 @@
+use nih_plug::prelude::*;
+
+/// how many times a second the puck gets moved, the same at every sample rate so renders match
+pub const SIMULATION_RATE: f32 = 1000.0;
+/// how long the puck stays in hand after the target last moved, in simulation steps
+const GRAB_STEPS: u32 = 50;
+
+/// what the spring pulls the puck towards
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpringTarget {
+    #[id = "center"]
+    #[name = "Center"]
+    Center,
+    /// whichever corner is closest right now
+    #[id = "nearest-corner"]
+    #[name = "Nearest Corner"]
+    NearestCorner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PuckSettings {
+    pub mass: f32,
+    /// how hard the spring pulls, 0 turns it off
+    pub spring: f32,
+    pub spring_target: SpringTarget,
+    /// friction, slows the puck down proportional to its speed
+    pub damping: f32,
+    /// how much speed is left after hitting a wall, 0 stops dead and 1 loses nothing
+    pub bounce: f32,
+}
+
+impl Default for PuckSettings {
+    fn default() -> Self {
+        Self {
+            mass: 1.0,
+            spring: 0.0,
+            spring_target: SpringTarget::Center,
+            damping: 2.0,
+            bounce: 0.7,
+        }
+    }
+}
+
+/**
+a puck sliding around the xy plane. while the target is moving the puck is held
+and follows it, once the target stops it is let go with the speed it was thrown at.
+
+the simulation runs in fixed steps of [`SIMULATION_RATE`] no matter the buffer size,
+so the same input always makes the same movement.
+call `set_settings` once per buffer and `process` for every sample
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Puck {
+    settings: PuckSettings,
+    /// simulation steps per sample
+    steps_per_sample: f32,
+    /// how far into the next step this sample is
+    phase: f32,
+    /// where the puck was one step ago, the output is blended between this and `position`
+    previous: [f32; 2],
+    position: [f32; 2],
+    /// in plane widths per second
+    velocity: [f32; 2],
+    target: [f32; 2],
+    /// steps since the target last moved
+    since_moved: u32,
+}
+
+impl Puck {
+    /// puts the puck down at `position`, standing still
+    pub fn reset(&mut self, position: [f32; 2]) {
+        self.previous = position;
+        self.position = position;
+        self.target = position;
+        self.velocity = [0.0; 2];
+        self.since_moved = GRAB_STEPS;
+        self.phase = 0.0;
+    }
+
+    pub fn set_settings(&mut self, settings: &PuckSettings, sample_rate: f32) {
+        self.settings = *settings;
+        self.steps_per_sample = SIMULATION_RATE / sample_rate.max(1.0);
+    }
+
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    pub fn velocity(&self) -> [f32; 2] {
+        self.velocity
+    }
+
+    /// moves one sample with `target` being where the hand is, returns where the puck is
+    pub fn process(&mut self, target: [f32; 2]) -> [f32; 2] {
+        self.phase += self.steps_per_sample;
+        while self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.previous = self.position;
+            self.step(target);
+        }
+        std::array::from_fn(|axis| {
+            self.previous[axis] + (self.position[axis] - self.previous[axis]) * self.phase
+        })
+    }
+
+    fn step(&mut self, target: [f32; 2]) {
+        let delta = SIMULATION_RATE.recip();
+
+        if target != self.target {
+            // thrown as fast as the hand moved since it last moved
+            let elapsed = (self.since_moved + 1) as f32 * delta;
+            self.velocity =
+                std::array::from_fn(|axis| (target[axis] - self.target[axis]) / elapsed);
+            self.target = target;
+            self.position = target;
+            self.since_moved = 0;
+            return;
+        }
+        self.since_moved = self.since_moved.saturating_add(1);
+        if self.since_moved < GRAB_STEPS {
+            self.position = target;
+            return;
+        }
+
+        let settings = &self.settings;
+        let mass = settings.mass.max(0.001);
+        for axis in 0..2 {
+            let anchor = match settings.spring_target {
+                SpringTarget::Center => 0.5,
+                SpringTarget::NearestCorner => self.position[axis].round(),
+            };
+            let force = settings.spring * (anchor - self.position[axis])
+                - settings.damping * self.velocity[axis];
+            self.velocity[axis] += force / mass * delta;
+            self.position[axis] += self.velocity[axis] * delta;
+
+            // bounces off the walls, the distance that went past them goes back the other way
+            let position = &mut self.position[axis];
+            let velocity = &mut self.velocity[axis];
+            if *position < 0.0 || *position > 1.0 {
+                *position = match *position < 0.0 {
+                    true => -*position * settings.bounce,
+                    false => 1.0 - (*position - 1.0) * settings.bounce,
+                };
+                *position = position.clamp(0.0, 1.0);
+                *velocity = -*velocity * settings.bounce;
+            }
+        }
+    }
+}
+
+#[derive(Params)]
+pub struct PuckParams {
+    #[id = "puck"]
+    pub enabled: BoolParam,
+    #[id = "puck-mass"]
+    pub mass: FloatParam,
+    #[id = "puck-spring"]
+    pub spring: FloatParam,
+    #[id = "puck-spring-target"]
+    pub spring_target: EnumParam<SpringTarget>,
+    #[id = "puck-damping"]
+    pub damping: FloatParam,
+    #[id = "puck-bounce"]
+    pub bounce: FloatParam,
+}
+
+impl Default for PuckParams {
+    fn default() -> Self {
+        let defaults = PuckSettings::default();
+
+        Self {
+            enabled: BoolParam::new("Puck", false),
+            mass: FloatParam::new(
+                "Puck Mass",
+                defaults.mass,
+                FloatRange::Skewed {
+                    min: 0.05,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" kg")
+            .with_step_size(0.01),
+            spring: FloatParam::new(
+                "Puck Spring",
+                defaults.spring,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 200.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" N/m")
+            .with_step_size(0.01),
+            spring_target: EnumParam::new("Spring Target", defaults.spring_target),
+            damping: FloatParam::new(
+                "Puck Damping",
+                defaults.damping,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 50.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" kg/s")
+            .with_step_size(0.01),
+            bounce: FloatParam::new(
+                "Puck Bounce",
+                defaults.bounce,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+        }
+    }
+}
+
+impl PuckParams {
+    pub fn settings(&self) -> PuckSettings {
+        PuckSettings {
+            mass: self.mass.value(),
+            spring: self.spring.value(),
+            spring_target: self.spring_target.value(),
+            damping: self.damping.value(),
+            bounce: self.bounce.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    /// one simulation step per sample keeps the numbers easy to follow
+    fn puck(settings: PuckSettings) -> Puck {
+        let mut puck = Puck::default();
+        puck.set_settings(&settings, SIMULATION_RATE);
+        puck.reset([0.5, 0.5]);
+        puck
+    }
+
+    /// moves the target from `from` to `to` over `steps`, then lets go
+    fn throw(puck: &mut Puck, from: [f32; 2], to: [f32; 2], steps: usize) {
+        for step in 1..=steps {
+            let amount = step as f32 / steps as f32;
+            puck.process(std::array::from_fn(|axis| {
+                from[axis] + (to[axis] - from[axis]) * amount
+            }));
+        }
+    }
+
+    #[test]
+    fn test_flick_keeps_moving() {
+        let mut puck = puck(PuckSettings {
+            damping: 0.0,
+            ..PuckSettings::default()
+        });
+        throw(&mut puck, [0.5, 0.5], [0.6, 0.5], 100);
+        assert_approx_eq!(f32, puck.velocity()[0], 1.0, epsilon = 0.0001);
+
+        // held while the hand might still move, then slides on
+        for _ in 0..GRAB_STEPS {
+            puck.process([0.6, 0.5]);
+        }
+        let released = puck.position()[0];
+        for _ in 0..100 {
+            puck.process([0.6, 0.5]);
+        }
+        assert_approx_eq!(f32, puck.position()[0] - released, 0.1, epsilon = 0.0001);
+        assert_eq!(puck.position()[1], 0.5);
+    }
+
+    #[test]
+    fn test_bounces_off_walls() {
+        let mut puck = puck(PuckSettings {
+            damping: 0.0,
+            bounce: 0.5,
+            ..PuckSettings::default()
+        });
+        throw(&mut puck, [0.5, 0.5], [0.9, 0.5], 100);
+        for _ in 0..300 {
+            let position = puck.process([0.9, 0.5]);
+            assert!((0.0..=1.0).contains(&position[0]));
+        }
+        // came back off the right wall at half the speed
+        assert_approx_eq!(f32, puck.velocity()[0], -2.0, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_spring_settles() {
+        let settings = PuckSettings {
+            spring: 50.0,
+            damping: 10.0,
+            ..PuckSettings::default()
+        };
+        let mut puck = puck(settings);
+        puck.reset([0.2, 0.9]);
+        for _ in 0..5000 {
+            puck.process([0.2, 0.9]);
+        }
+        assert_approx_eq!(f32, puck.position()[0], 0.5, epsilon = 0.001);
+        assert_approx_eq!(f32, puck.position()[1], 0.5, epsilon = 0.001);
+
+        let mut puck = self::puck(PuckSettings {
+            spring_target: SpringTarget::NearestCorner,
+            ..settings
+        });
+        puck.reset([0.2, 0.9]);
+        for _ in 0..5000 {
+            puck.process([0.2, 0.9]);
+        }
+        assert_approx_eq!(f32, puck.position()[0], 0.0, epsilon = 0.001);
+        assert_approx_eq!(f32, puck.position()[1], 1.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_same_movement_at_any_sample_rate() {
+        // the simulation steps land in the same places whatever the sample rate
+        let run = |sample_rate: f32| {
+            let mut puck = Puck::default();
+            puck.set_settings(&PuckSettings::default(), sample_rate);
+            puck.reset([0.5, 0.5]);
+            let samples = (sample_rate / 10.0) as usize;
+            throw(&mut puck, [0.5, 0.5], [0.7, 0.4], samples);
+            for _ in 0..samples * 5 {
+                puck.process([0.7, 0.4]);
+            }
+            puck.position()
+        };
+        assert_eq!(run(2000.0), run(4000.0));
+    }
+}
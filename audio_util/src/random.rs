@@ -0,0 +1,89 @@
+/**
+a small seeded random number generator. nothing fancy, but the same seed always
+gives the same numbers on every machine, so offline renders come out the same
+ */
+#[derive(Debug, Clone)]
+pub struct Xorshift {
+    state: u32,
+}
+
+impl Xorshift {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            // xorshift gets stuck on zero
+            state: seed.max(1),
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    /// a random value from -1 to 1
+    pub fn next_bipolar(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+}
+
+/// a random value from -1 to 1 that only depends on `seed` and `index`
+pub fn hashed_bipolar(seed: u32, index: u32) -> f32 {
+    let mut hash = seed.wrapping_mul(0x9e37_79b9) ^ index;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846c_a68b);
+    hash ^= hash >> 16;
+    (hash >> 8) as f32 / (1 << 23) as f32 - 1.0
+}
+
+/**
+one dimensional perlin noise, smooth and roughly from -1 to 1.
+it goes through 0 at every whole `position`, different seeds give different noise
+ */
+pub fn gradient_noise(seed: u32, position: f64) -> f32 {
+    let index = position.floor();
+    let amount = (position - index) as f32;
+    let index = index as i64 as u32;
+    let start = hashed_bipolar(seed, index) * amount;
+    let end = hashed_bipolar(seed, index.wrapping_add(1)) * (amount - 1.0);
+    let fade = amount * amount * amount * (amount * (amount * 6.0 - 15.0) + 10.0);
+    2.0 * (start + (end - start) * fade)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xorshift() {
+        let mut random = Xorshift::new(7);
+        let values: Vec<f32> = (0..1000).map(|_| random.next_bipolar()).collect();
+        assert!(values.iter().all(|value| (-1.0..1.0).contains(value)));
+        assert!(values.iter().any(|value| *value > 0.5));
+        assert!(values.iter().any(|value| *value < -0.5));
+
+        // the same seed gives the same values, zero still gives some
+        let mut again = Xorshift::new(7);
+        assert!(values.iter().all(|value| *value == again.next_bipolar()));
+        assert_ne!(Xorshift::new(0).next_u32(), 0);
+    }
+
+    #[test]
+    fn test_gradient_noise() {
+        assert_eq!(gradient_noise(3, 5.0), 0.0);
+        assert_ne!(gradient_noise(3, 5.5), gradient_noise(4, 5.5));
+        assert_eq!(gradient_noise(3, 5.5), gradient_noise(3, 5.5));
+
+        // smooth, small steps never jump
+        let values: Vec<f32> = (0..10000)
+            .map(|index| gradient_noise(9, index as f64 * 0.01 - 50.0))
+            .collect();
+        assert!(values.iter().all(|value| (-1.0..=1.0).contains(value)));
+        assert!(values
+            .windows(2)
+            .all(|pair| (pair[0] - pair[1]).abs() < 0.05));
+    }
+}
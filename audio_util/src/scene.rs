@@ -0,0 +1,289 @@
+use crate::{
+    crossfade::Crossfade,
+    midi::{MidiControl, MAX_AXES},
+};
+use nih_plug::prelude::*;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// how many scenes can be stored
+pub const SCENES: usize = 16;
+
+/// fader positions and curve settings stored to come back to later
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub positions: [f32; MAX_AXES],
+    pub crossfade: Crossfade,
+}
+
+/// every scene slot, `None` for the empty ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenes {
+    scenes: Vec<Option<Scene>>,
+}
+
+impl Default for Scenes {
+    fn default() -> Self {
+        Self {
+            scenes: vec![None; SCENES],
+        }
+    }
+}
+
+impl Scenes {
+    pub fn get(&self, index: usize) -> Option<Scene> {
+        self.scenes.get(index).copied().flatten()
+    }
+
+    pub fn set(&mut self, index: usize, scene: Scene) {
+        if let Some(slot) = self.scenes.get_mut(index) {
+            *slot = Some(scene);
+        }
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        if let Some(slot) = self.scenes.get_mut(index) {
+            *slot = None;
+        }
+    }
+
+    pub fn is_stored(&self, index: usize) -> bool {
+        self.get(index).is_some()
+    }
+}
+
+/**
+stores and recalls scenes. the positions get moved by a [`MidiControl`] just like notes
+move them, this morphs the curve settings alongside.
+
+`load` the scenes in `initialize`, call `update` once per buffer after
+`MidiControl::follow_faders`, `next_curves` for every sample and `publish` at the end
+of the buffer
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SceneMorph {
+    scenes: Scenes,
+    /// a scene was stored since they were last written to the plugin state
+    dirty: bool,
+    from: Crossfade,
+    to: Crossfade,
+    /// 0 at `from`, 1 at `to`
+    amount: f32,
+    step: f32,
+    /// the curve params when the scene was recalled, `None` while the params are in control
+    held: Option<Crossfade>,
+    /// the scene param last buffer, a different one recalls that scene
+    last_scene: Option<i32>,
+    /// the store button last buffer, every flip stores
+    last_store: Option<bool>,
+    /// the recall button last buffer, every flip recalls
+    last_recall: Option<bool>,
+}
+
+impl SceneMorph {
+    pub fn reset(&mut self) {
+        self.held = None;
+    }
+
+    /// copies the scenes out of the plugin state
+    pub fn load(&mut self, scenes: &RwLock<Scenes>) {
+        self.scenes.clone_from(&scenes.read());
+        self.dirty = false;
+    }
+
+    /// writes the scenes back to the plugin state, tries again next buffer when it is in use
+    pub fn publish(&mut self, scenes: &RwLock<Scenes>) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(mut scenes) = scenes.try_write() {
+            // both always hold every scene, so this doesn't allocate
+            scenes.clone_from(&self.scenes);
+            self.dirty = false;
+        }
+    }
+
+    /**
+    stores the scene when the store button flipped and recalls it when the recall button
+    flipped or the scene param changed. the curve goes back to the params once they move
+     */
+    pub fn update(
+        &mut self,
+        params: &SceneParams,
+        crossfade: &Crossfade,
+        midi: &mut MidiControl,
+        sample_rate: f32,
+    ) {
+        self.follow_curve(crossfade);
+
+        let scene = params.scene.value();
+        let index = (scene - 1).max(0) as usize;
+        let store = params.store.value();
+        let recall = params.recall.value();
+        if self.last_store.is_some_and(|last| last != store) {
+            self.store(index, crossfade, midi);
+        }
+        if self.last_scene.is_some_and(|last| last != scene)
+            || self.last_recall.is_some_and(|last| last != recall)
+        {
+            let samples = params.morph_time.value() / 1000.0 * sample_rate;
+            self.recall(index, samples, crossfade, midi);
+        }
+        self.last_scene = Some(scene);
+        self.last_store = Some(store);
+        self.last_recall = Some(recall);
+    }
+
+    /// hands the curve back to the params when they moved since the recall
+    pub fn follow_curve(&mut self, crossfade: &Crossfade) {
+        if self.held.is_some_and(|held| held != *crossfade) {
+            self.held = None;
+        }
+    }
+
+    /// stores where the axes and the curve are right now
+    pub fn store(&mut self, index: usize, crossfade: &Crossfade, midi: &MidiControl) {
+        let scene = Scene {
+            positions: midi.positions(),
+            crossfade: self.current(crossfade),
+        };
+        self.scenes.set(index, scene);
+        self.dirty = true;
+    }
+
+    /// starts morphing to a stored scene over `samples`, empty slots do nothing
+    pub fn recall(
+        &mut self,
+        index: usize,
+        samples: f32,
+        crossfade: &Crossfade,
+        midi: &mut MidiControl,
+    ) {
+        let Some(scene) = self.scenes.get(index) else {
+            return;
+        };
+        midi.recall(scene.positions, samples);
+
+        self.from = self.current(crossfade);
+        self.to = scene.crossfade;
+        self.held = Some(*crossfade);
+        (self.amount, self.step) = match samples >= 1.0 {
+            true => (0.0, samples.recip()),
+            false => (1.0, 0.0),
+        };
+    }
+
+    /// the curve that is mostly in use right now
+    fn current(&self, crossfade: &Crossfade) -> Crossfade {
+        match (self.held, self.amount < 0.5) {
+            (None, _) => *crossfade,
+            (Some(_), true) => self.from,
+            (Some(_), false) => self.to,
+        }
+    }
+
+    /// advances one sample, returns the curves to blend from and to and how far along it is
+    pub fn next_curves(&mut self, crossfade: &Crossfade) -> (Crossfade, Crossfade, f32) {
+        match self.held {
+            Some(_) => {
+                self.amount = (self.amount + self.step).min(1.0);
+                (self.from, self.to, self.amount)
+            }
+            None => (*crossfade, *crossfade, 0.0),
+        }
+    }
+}
+
+#[derive(Params)]
+pub struct SceneParams {
+    /// changing this recalls the scene
+    #[id = "scene"]
+    pub scene: IntParam,
+    /// every press stores the current positions and curve in the selected scene
+    #[id = "store-scene"]
+    pub store: BoolParam,
+    /// every press recalls the selected scene again
+    #[id = "recall-scene"]
+    pub recall: BoolParam,
+    #[id = "scene-morph"]
+    pub morph_time: FloatParam,
+    #[persist = "scenes"]
+    pub scenes: RwLock<Scenes>,
+}
+
+impl Default for SceneParams {
+    fn default() -> Self {
+        Self {
+            scene: IntParam::new(
+                "Scene",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: SCENES as i32,
+                },
+            ),
+            store: BoolParam::new("Store Scene", false),
+            recall: BoolParam::new("Recall Scene", false),
+            morph_time: FloatParam::new(
+                "Scene Morph",
+                500.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 10000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+            scenes: RwLock::new(Scenes::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossfade::CrossfadeCurve;
+
+    #[test]
+    fn test_store_and_recall() {
+        let linear = Crossfade::new(CrossfadeCurve::Linear);
+        let power = Crossfade::new(CrossfadeCurve::ConstantPower);
+        let mut midi = MidiControl::default();
+        let mut morph = SceneMorph::default();
+
+        let mut positions = [0.1, 0.9, 0.5];
+        midi.next(&mut positions);
+        morph.store(3, &power, &midi);
+        let scenes = RwLock::new(Scenes::default());
+        morph.publish(&scenes);
+        assert_eq!(
+            scenes.read().get(3),
+            Some(Scene {
+                positions: [0.1, 0.9, 0.5],
+                crossfade: power,
+            })
+        );
+        assert!(!scenes.read().is_stored(4));
+
+        // empty slots leave everything alone
+        morph.recall(4, 2.0, &linear, &mut midi);
+        assert_eq!(morph.next_curves(&linear), (linear, linear, 0.0));
+
+        let mut positions = [0.5; 3];
+        midi.next(&mut positions);
+        morph.recall(3, 2.0, &linear, &mut midi);
+        assert_eq!(morph.next_curves(&linear), (linear, power, 0.5));
+        midi.next(&mut positions);
+        assert_eq!(positions, [0.3, 0.7, 0.5]);
+        assert_eq!(morph.next_curves(&linear), (linear, power, 1.0));
+        midi.next(&mut positions);
+        assert_eq!(positions, [0.1, 0.9, 0.5]);
+
+        // stays on the scene until the curve params change
+        morph.follow_curve(&linear);
+        assert_eq!(morph.next_curves(&linear), (linear, power, 1.0));
+        morph.follow_curve(&power);
+        assert_eq!(morph.next_curves(&power), (power, power, 0.0));
+    }
+}
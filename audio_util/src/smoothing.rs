@@ -0,0 +1,261 @@
+use nih_plug::prelude::*;
+
+/// logarithmic ramps move in equal ratios, this keeps them away from zero
+const LOGARITHMIC_OFFSET: f32 = 0.01;
+/// how close an exponential ramp gets to its target before it jumps the rest of the way
+const EXPONENTIAL_REMAINDER: f32 = 0.0001;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmoothingMode {
+    /// same speed all the way, gets there in the smoothing time
+    #[id = "linear"]
+    #[name = "Linear"]
+    Linear,
+    /// fast at first and slow at the end, gets there in the smoothing time
+    #[id = "exponential"]
+    #[name = "Exponential"]
+    Exponential,
+    /// slow near 0 and fast near 1, gets there in the smoothing time
+    #[id = "logarithmic"]
+    #[name = "Logarithmic"]
+    Logarithmic,
+    /// never moves faster than the max speed, so longer moves take longer
+    #[id = "glide"]
+    #[name = "Glide"]
+    Glide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothingSettings {
+    pub mode: SmoothingMode,
+    pub time_ms: f32,
+    /// how much of the fader glide can travel per second
+    pub max_speed: f32,
+}
+
+impl Default for SmoothingSettings {
+    fn default() -> Self {
+        Self {
+            mode: SmoothingMode::Linear,
+            time_ms: 10.0,
+            max_speed: 1.0,
+        }
+    }
+}
+
+/**
+smooths one fader axis. unlike the param smoothers the mode can change while running,
+and the target can come from anywhere, not just the param.
+
+call `set_settings` once per buffer and `process` for every sample
+ */
+#[derive(Debug, Clone, Default)]
+pub struct AxisSmoother {
+    settings: SmoothingSettings,
+    sample_rate: f32,
+    current: f32,
+    target: f32,
+    /// added for linear ramps, the remaining distance is scaled by it for exponential ones
+    /// and the offset value multiplied by it for logarithmic ones
+    step: f32,
+    steps_left: u32,
+}
+
+impl AxisSmoother {
+    /// jumps straight to `value`
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.steps_left = 0;
+    }
+
+    pub fn set_settings(&mut self, settings: &SmoothingSettings, sample_rate: f32) {
+        if *settings == self.settings && sample_rate == self.sample_rate {
+            return;
+        }
+        self.settings = *settings;
+        self.sample_rate = sample_rate;
+        // a running ramp carries on from where it is with the new settings
+        if self.steps_left > 0 {
+            self.start(self.target);
+        }
+    }
+
+    /// the position right now, without moving
+    pub fn value(&self) -> f32 {
+        self.current
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.current != self.target
+    }
+
+    /// moves one sample towards `target` and returns where it got to
+    pub fn process(&mut self, target: f32) -> f32 {
+        if target != self.target {
+            self.start(target);
+        }
+
+        match self.settings.mode {
+            SmoothingMode::Glide => {
+                let max_step = self.settings.max_speed / self.sample_rate.max(1.0);
+                self.current += (self.target - self.current).clamp(-max_step, max_step);
+            }
+            _ if self.steps_left == 0 => self.current = self.target,
+            mode => {
+                self.steps_left -= 1;
+                self.current = match (self.steps_left, mode) {
+                    (0, _) => self.target,
+                    (_, SmoothingMode::Exponential) => {
+                        self.target + (self.current - self.target) * self.step
+                    }
+                    (_, SmoothingMode::Logarithmic) => {
+                        (self.current + LOGARITHMIC_OFFSET) * self.step - LOGARITHMIC_OFFSET
+                    }
+                    _ => self.current + self.step,
+                };
+            }
+        }
+        self.current
+    }
+
+    fn start(&mut self, target: f32) {
+        self.target = target;
+        let steps = (self.settings.time_ms / 1000.0 * self.sample_rate).round();
+        self.steps_left = steps as u32;
+        if self.steps_left == 0 {
+            return;
+        }
+
+        self.step = match self.settings.mode {
+            SmoothingMode::Linear => (target - self.current) / steps,
+            SmoothingMode::Exponential => EXPONENTIAL_REMAINDER.powf(steps.recip()),
+            SmoothingMode::Logarithmic => ((target + LOGARITHMIC_OFFSET)
+                / (self.current + LOGARITHMIC_OFFSET).max(f32::EPSILON))
+            .powf(steps.recip()),
+            SmoothingMode::Glide => 0.0,
+        };
+    }
+}
+
+#[derive(Params)]
+pub struct SmoothingParams {
+    #[id = "smoothing-mode"]
+    pub mode: EnumParam<SmoothingMode>,
+    #[id = "smoothing-time"]
+    pub time: FloatParam,
+    #[id = "glide-speed"]
+    pub max_speed: FloatParam,
+}
+
+impl Default for SmoothingParams {
+    fn default() -> Self {
+        let defaults = SmoothingSettings::default();
+
+        Self {
+            mode: EnumParam::new("Smoothing", defaults.mode),
+            time: FloatParam::new(
+                "Smoothing Time",
+                defaults.time_ms,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 5000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+            max_speed: FloatParam::new(
+                "Glide Speed",
+                defaults.max_speed,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 100.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" %/s")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+        }
+    }
+}
+
+impl SmoothingParams {
+    pub fn settings(&self) -> SmoothingSettings {
+        SmoothingSettings {
+            mode: self.mode.value(),
+            time_ms: self.time.value(),
+            max_speed: self.max_speed.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    fn smoother(mode: SmoothingMode) -> AxisSmoother {
+        let mut smoother = AxisSmoother::default();
+        smoother.set_settings(
+            &SmoothingSettings {
+                mode,
+                time_ms: 4.0,
+                max_speed: 250.0,
+            },
+            1000.0,
+        );
+        smoother
+    }
+
+    fn ramp(smoother: &mut AxisSmoother, target: f32, samples: usize) -> Vec<f32> {
+        (0..samples).map(|_| smoother.process(target)).collect()
+    }
+
+    #[test]
+    fn test_linear() {
+        let mut smoother = smoother(SmoothingMode::Linear);
+        assert_eq!(ramp(&mut smoother, 1.0, 5), [0.25, 0.5, 0.75, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_exponential_and_logarithmic_arrive_in_time() {
+        for mode in [SmoothingMode::Exponential, SmoothingMode::Logarithmic] {
+            let mut smoother = smoother(mode);
+            let values = ramp(&mut smoother, 1.0, 4);
+            assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+            assert_eq!(values[3], 1.0);
+        }
+
+        // exponential moves most at the start, logarithmic at the end
+        let first = |mode| ramp(&mut smoother(mode), 1.0, 1)[0];
+        assert!(first(SmoothingMode::Exponential) > 0.25);
+        assert!(first(SmoothingMode::Logarithmic) < 0.25);
+    }
+
+    #[test]
+    fn test_glide_speed_limit() {
+        let mut smoother = smoother(SmoothingMode::Glide);
+        assert_eq!(ramp(&mut smoother, 0.5, 3), [0.25, 0.5, 0.5]);
+        // turning around is limited just the same
+        assert_eq!(ramp(&mut smoother, 0.0, 2), [0.25, 0.0]);
+    }
+
+    #[test]
+    fn test_zero_time_jumps() {
+        let mut smoother = AxisSmoother::default();
+        smoother.set_settings(
+            &SmoothingSettings {
+                time_ms: 0.0,
+                ..SmoothingSettings::default()
+            },
+            1000.0,
+        );
+        assert_eq!(smoother.process(0.7), 0.7);
+
+        smoother.reset(0.2);
+        assert_approx_eq!(f32, smoother.value(), 0.2);
+        assert!(!smoother.is_smoothing());
+    }
+}
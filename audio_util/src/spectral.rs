@@ -0,0 +1,298 @@
+use nih_plug::prelude::Enum;
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+use std::{f32::consts::TAU, sync::Arc};
+
+/// how many frames overlap, a hann window at 4x overlap adds back up to a constant
+const OVERLAP: usize = 4;
+/// what a hann window squared adds up to at 4x overlap
+const WINDOW_SUM: f32 = 1.5;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FftSize {
+    #[id = "512"]
+    #[name = "512"]
+    Size512,
+    #[id = "1024"]
+    #[name = "1024"]
+    Size1024,
+    #[id = "2048"]
+    #[name = "2048"]
+    Size2048,
+    #[id = "4096"]
+    #[name = "4096"]
+    Size4096,
+}
+
+impl FftSize {
+    pub const ALL: [FftSize; 4] = [
+        FftSize::Size512,
+        FftSize::Size1024,
+        FftSize::Size2048,
+        FftSize::Size4096,
+    ];
+
+    pub fn samples(self) -> usize {
+        512 << self as usize
+    }
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectralMorphMode {
+    /// fades the magnitude of every bin on its own
+    #[id = "magnitude"]
+    #[name = "Magnitude"]
+    Magnitude,
+    /// splits every spectrum into a smoothed envelope and the detail on top of it,
+    /// the envelopes are interpolated geometrically per bin and the detail linearly
+    #[id = "envelope"]
+    #[name = "Envelope"]
+    Envelope,
+}
+
+/**
+morphs between two signals in the frequency domain.
+every frame the magnitudes of both spectra are interpolated,
+the phase comes from the plain crossfade of both spectra so the endpoints
+give back the original signals.
+
+the output is delayed by `latency()` samples
+ */
+pub struct SpectralMorph {
+    size: usize,
+    hop: usize,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    window: Vec<f32>,
+    /// the last `size` samples of both inputs, written round and round
+    main_input: Vec<f32>,
+    side_input: Vec<f32>,
+    /// overlap added output, read and cleared one sample at a time
+    output: Vec<f32>,
+    index: usize,
+    samples_until_frame: usize,
+    main_frame: Vec<f32>,
+    side_frame: Vec<f32>,
+    main_spectrum: Vec<Complex<f32>>,
+    side_spectrum: Vec<Complex<f32>>,
+    main_envelope: Vec<f32>,
+    side_envelope: Vec<f32>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl SpectralMorph {
+    /// allocates everything, so call this outside of the audio thread
+    pub fn new(size: FftSize) -> Self {
+        let size = size.samples();
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(size);
+        let inverse = planner.plan_fft_inverse(size);
+        let scratch_len = forward.get_scratch_len().max(inverse.get_scratch_len());
+        let bins = size / 2 + 1;
+
+        Self {
+            size,
+            hop: size / OVERLAP,
+            window: (0..size)
+                .map(|index| 0.5 - 0.5 * (TAU * index as f32 / size as f32).cos())
+                .collect(),
+            main_input: vec![0.0; size],
+            side_input: vec![0.0; size],
+            output: vec![0.0; size],
+            index: 0,
+            samples_until_frame: size / OVERLAP,
+            main_frame: vec![0.0; size],
+            side_frame: vec![0.0; size],
+            main_spectrum: vec![Complex::default(); bins],
+            side_spectrum: vec![Complex::default(); bins],
+            main_envelope: vec![0.0; bins],
+            side_envelope: vec![0.0; bins],
+            scratch: vec![Complex::default(); scratch_len],
+            forward,
+            inverse,
+        }
+    }
+
+    /// how many samples the output lags behind the input
+    pub fn latency(&self) -> u32 {
+        self.size as u32
+    }
+
+    pub fn reset(&mut self) {
+        self.main_input.fill(0.0);
+        self.side_input.fill(0.0);
+        self.output.fill(0.0);
+        self.index = 0;
+        self.samples_until_frame = self.hop;
+    }
+
+    /// feeds one sample of each input, `position` 0 is all main and 1 is all side
+    pub fn process(&mut self, main: f32, side: f32, position: f32, mode: SpectralMorphMode) -> f32 {
+        self.main_input[self.index] = main;
+        self.side_input[self.index] = side;
+        let output = std::mem::take(&mut self.output[self.index]);
+        self.index = (self.index + 1) % self.size;
+
+        self.samples_until_frame -= 1;
+        if self.samples_until_frame == 0 {
+            self.samples_until_frame = self.hop;
+            self.process_frame(position.clamp(0.0, 1.0), mode);
+        }
+
+        output
+    }
+
+    fn process_frame(&mut self, position: f32, mode: SpectralMorphMode) {
+        // `index` points at the oldest sample now
+        for offset in 0..self.size {
+            let read = (self.index + offset) % self.size;
+            self.main_frame[offset] = self.main_input[read] * self.window[offset];
+            self.side_frame[offset] = self.side_input[read] * self.window[offset];
+        }
+
+        let scratch_len = self.forward.get_scratch_len();
+        // the lengths always match, so these can't fail
+        let _ = self.forward.process_with_scratch(
+            &mut self.main_frame,
+            &mut self.main_spectrum,
+            &mut self.scratch[..scratch_len],
+        );
+        let _ = self.forward.process_with_scratch(
+            &mut self.side_frame,
+            &mut self.side_spectrum,
+            &mut self.scratch[..scratch_len],
+        );
+
+        if mode == SpectralMorphMode::Envelope {
+            envelope(&self.main_spectrum, &mut self.main_envelope);
+            envelope(&self.side_spectrum, &mut self.side_envelope);
+        }
+
+        for (bin, (main, side)) in self
+            .main_spectrum
+            .iter_mut()
+            .zip(&self.side_spectrum)
+            .enumerate()
+        {
+            let (main_magnitude, side_magnitude) = (main.norm(), side.norm());
+            let magnitude = match mode {
+                SpectralMorphMode::Magnitude => lerp(main_magnitude, side_magnitude, position),
+                SpectralMorphMode::Envelope => {
+                    let (main_envelope, side_envelope) =
+                        (self.main_envelope[bin], self.side_envelope[bin]);
+                    // geometric, so halfway is the per bin geometric mean of both envelopes
+                    let envelope =
+                        main_envelope.powf(1.0 - position) * side_envelope.powf(position);
+                    envelope
+                        * lerp(
+                            main_magnitude / main_envelope,
+                            side_magnitude / side_envelope,
+                            position,
+                        )
+                }
+            };
+
+            let phase = *main * (1.0 - position) + *side * position;
+            let phase_norm = phase.norm();
+            *main = match phase_norm > f32::EPSILON {
+                true => phase * (magnitude / phase_norm),
+                false => Complex::new(magnitude, 0.0),
+            };
+        }
+
+        // dc and nyquist have to be real for the inverse transform
+        let last = self.main_spectrum.len() - 1;
+        self.main_spectrum[0].im = 0.0;
+        self.main_spectrum[last].im = 0.0;
+
+        let scratch_len = self.inverse.get_scratch_len();
+        let _ = self.inverse.process_with_scratch(
+            &mut self.main_spectrum,
+            &mut self.main_frame,
+            &mut self.scratch[..scratch_len],
+        );
+
+        let gain = 1.0 / (self.size as f32 * WINDOW_SUM);
+        for offset in 0..self.size {
+            let write = (self.index + offset) % self.size;
+            self.output[write] += self.main_frame[offset] * self.window[offset] * gain;
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, position: f32) -> f32 {
+    a + (b - a) * position
+}
+
+/// smooths the magnitudes across neighbouring bins, never exactly zero so it can be divided by
+fn envelope(spectrum: &[Complex<f32>], envelope: &mut [f32]) {
+    let width = (spectrum.len() / 64).max(1);
+    let mut sum: f32 = spectrum.iter().take(width).map(|bin| bin.norm()).sum();
+    let mut count = width.min(spectrum.len());
+
+    for (bin, value) in envelope.iter_mut().enumerate() {
+        if let Some(entering) = spectrum.get(bin + width) {
+            sum += entering.norm();
+            count += 1;
+        }
+        if bin > width {
+            sum -= spectrum[bin - width - 1].norm();
+            count -= 1;
+        }
+        *value = (sum / count as f32).max(f32::EPSILON);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    fn run(position: f32, mode: SpectralMorphMode) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let mut morph = SpectralMorph::new(FftSize::Size512);
+        let main: Vec<f32> = (0..4096).map(|index| (index as f32 * 0.05).sin()).collect();
+        let side: Vec<f32> = (0..4096)
+            .map(|index| (index as f32 * 0.31).sin() * 0.5 + (index as f32 * 0.007).cos() * 0.25)
+            .collect();
+        let output = main
+            .iter()
+            .zip(&side)
+            .map(|(main, side)| morph.process(*main, *side, position, mode))
+            .collect();
+        (main, side, output)
+    }
+
+    fn assert_delayed(input: &[f32], output: &[f32], latency: usize) {
+        // the first frames still see the silence from before the signal started
+        for index in latency * 2..output.len() {
+            assert_approx_eq!(f32, output[index], input[index - latency], epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_endpoints() {
+        for mode in [SpectralMorphMode::Magnitude, SpectralMorphMode::Envelope] {
+            let (main, _, output) = run(0.0, mode);
+            assert_delayed(&main, &output, 512);
+
+            let (_, side, output) = run(1.0, mode);
+            assert_delayed(&side, &output, 512);
+        }
+    }
+
+    #[test]
+    fn test_middle_keeps_level() {
+        // two sines that don't share bins keep half their magnitude each
+        let (_, _, output) = run(0.5, SpectralMorphMode::Magnitude);
+        let peak = output[2048..]
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!(peak > 0.4 && peak < 1.0);
+    }
+
+    #[test]
+    fn test_fft_sizes() {
+        assert_eq!(FftSize::Size512.samples(), 512);
+        assert_eq!(FftSize::Size4096.samples(), 4096);
+        assert_eq!(SpectralMorph::new(FftSize::Size2048).latency(), 2048);
+    }
+}
@@ -0,0 +1,410 @@
+use crate::transition::Grid;
+use nih_plug::prelude::*;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// the most points a drawn path keeps, drawing past this stops adding points
+pub const MAX_POINTS: usize = 512;
+
+/// how the cursor gets from one drawn point to the next
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathShape {
+    /// straight lines between the points
+    #[id = "polyline"]
+    #[name = "Polyline"]
+    Polyline,
+    /// a catmull-rom spline through the points
+    #[id = "spline"]
+    #[name = "Spline"]
+    Spline,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// jumps back to the start at the end
+    #[id = "loop"]
+    #[name = "Loop"]
+    Loop,
+    /// runs back along the path at the end
+    #[id = "ping-pong"]
+    #[name = "Ping-Pong"]
+    PingPong,
+    /// stops at the end
+    #[id = "one-shot"]
+    #[name = "One Shot"]
+    OneShot,
+}
+
+/// a drawn path across the xy plane, every point goes from 0 to 1 on both axes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trajectory {
+    points: Vec<[f32; 2]>,
+}
+
+impl Trajectory {
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// adds a point to the end, returns false once the path is full
+    pub fn push(&mut self, point: [f32; 2]) -> bool {
+        if self.points.len() >= MAX_POINTS {
+            return false;
+        }
+        self.points.push(point.map(|value| value.clamp(0.0, 1.0)));
+        true
+    }
+}
+
+/// fills `lengths` with how far along the path every point is, the first one is at 0
+pub fn path_lengths(points: &[[f32; 2]], lengths: &mut Vec<f32>) {
+    lengths.clear();
+    let mut length = 0.0;
+    for (index, point) in points.iter().enumerate() {
+        if let Some(previous) = index.checked_sub(1).map(|previous| points[previous]) {
+            length += (point[0] - previous[0]).hypot(point[1] - previous[1]);
+        }
+        lengths.push(length);
+    }
+}
+
+/**
+the point `amount` of the way along the path, measured by distance so
+the cursor moves at the same speed no matter how fast the path was drawn.
+`lengths` comes from [`path_lengths`]
+ */
+pub fn path_point(points: &[[f32; 2]], lengths: &[f32], amount: f32, shape: PathShape) -> [f32; 2] {
+    let (Some(first), Some(total)) = (points.first(), lengths.last()) else {
+        return [0.5, 0.5];
+    };
+    if points.len() < 2 || *total <= 0.0 {
+        return *first;
+    }
+
+    let distance = amount.clamp(0.0, 1.0) * total;
+    let segment = lengths
+        .partition_point(|length| *length <= distance)
+        .clamp(1, points.len() - 1)
+        - 1;
+    let segment_length = lengths[segment + 1] - lengths[segment];
+    let amount = match segment_length > 0.0 {
+        true => ((distance - lengths[segment]) / segment_length).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+
+    let start = points[segment];
+    let end = points[segment + 1];
+    match shape {
+        PathShape::Polyline => {
+            std::array::from_fn(|axis| start[axis] + (end[axis] - start[axis]) * amount)
+        }
+        PathShape::Spline => {
+            let before = points[segment.saturating_sub(1)];
+            let after = points[(segment + 2).min(points.len() - 1)];
+            std::array::from_fn(|axis| {
+                catmull_rom(before[axis], start[axis], end[axis], after[axis], amount)
+                    .clamp(0.0, 1.0)
+            })
+        }
+    }
+}
+
+fn catmull_rom(before: f32, start: f32, end: f32, after: f32, amount: f32) -> f32 {
+    let squared = amount * amount;
+    0.5 * (2.0 * start
+        + (end - before) * amount
+        + (2.0 * before - 5.0 * start + 4.0 * end - after) * squared
+        + (3.0 * start - before - 3.0 * end + after) * squared * amount)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectorySettings {
+    pub shape: PathShape,
+    pub mode: PlaybackMode,
+    pub sync: bool,
+    /// how long one run along the path takes when synced
+    pub length_bars: f32,
+    /// how long one run along the path takes when not synced or without a tempo
+    pub length_seconds: f32,
+}
+
+/**
+moves the cursor along a drawn path while playing.
+call `sync_path` and `begin_block` once per buffer, then `next_point` for every sample
+ */
+#[derive(Debug, Clone)]
+pub struct TrajectoryPlayer {
+    /// a copy of the path, so the editor can keep drawing without blocking the audio thread
+    points: Vec<[f32; 2]>,
+    lengths: Vec<f32>,
+    /// how many runs along the path have happened, the fraction is how far into this one
+    time: f64,
+    increment: f64,
+    playing: bool,
+}
+
+impl Default for TrajectoryPlayer {
+    fn default() -> Self {
+        Self {
+            points: Vec::with_capacity(MAX_POINTS),
+            lengths: Vec::with_capacity(MAX_POINTS),
+            time: 0.0,
+            increment: 0.0,
+            playing: false,
+        }
+    }
+}
+
+impl TrajectoryPlayer {
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+    }
+
+    /// picks up a newly drawn path, keeps the old one while the editor is busy writing
+    pub fn sync_path(&mut self, path: &RwLock<Trajectory>) {
+        let Some(path) = path.try_read() else {
+            return;
+        };
+        if path.points() == self.points.as_slice() {
+            return;
+        }
+        // both have room for `MAX_POINTS`, so this doesn't allocate
+        self.points.clear();
+        self.points
+            .extend(path.points().iter().take(MAX_POINTS).copied());
+        path_lengths(&self.points, &mut self.lengths);
+    }
+
+    /**
+    starts from the beginning when `play` gets switched on and works out the speed.
+    synced loops follow the song position while the host is playing,
+    one shots always start where they were switched on
+     */
+    pub fn begin_block(
+        &mut self,
+        play: bool,
+        settings: &TrajectorySettings,
+        grid: &Grid,
+        sample_rate: f32,
+    ) {
+        if play && !self.playing {
+            self.time = 0.0;
+        }
+        self.playing = play;
+
+        let sample_rate = sample_rate.max(1.0) as f64;
+        match (settings.sync, grid.tempo.filter(|tempo| *tempo > 0.0)) {
+            (true, Some(tempo)) => {
+                let beats = (settings.length_bars as f64 * grid.beats_per_bar).max(f64::EPSILON);
+                self.increment = tempo / 60.0 / beats / sample_rate;
+                if let (Some(position), true, false) = (
+                    grid.pos_beats,
+                    grid.playing,
+                    settings.mode == PlaybackMode::OneShot,
+                ) {
+                    self.time = (position / beats).max(0.0);
+                }
+            }
+            _ => self.increment = 1.0 / (settings.length_seconds.max(0.001) as f64 * sample_rate),
+        }
+    }
+
+    /// advances by one sample, returns the cursor position while playing a path
+    pub fn next_point(&mut self, settings: &TrajectorySettings) -> Option<[f32; 2]> {
+        if !self.playing || self.points.is_empty() {
+            return None;
+        }
+
+        let amount = match settings.mode {
+            PlaybackMode::Loop => self.time.fract(),
+            PlaybackMode::PingPong => {
+                let time = self.time.rem_euclid(2.0);
+                match time < 1.0 {
+                    true => time,
+                    false => 2.0 - time,
+                }
+            }
+            PlaybackMode::OneShot => self.time.min(1.0),
+        };
+        self.time += self.increment;
+        Some(path_point(
+            &self.points,
+            &self.lengths,
+            amount as f32,
+            settings.shape,
+        ))
+    }
+}
+
+#[derive(Params)]
+pub struct TrajectoryParams {
+    #[id = "path-play"]
+    pub play: BoolParam,
+    #[id = "path-shape"]
+    pub shape: EnumParam<PathShape>,
+    #[id = "path-mode"]
+    pub mode: EnumParam<PlaybackMode>,
+    #[id = "path-sync"]
+    pub sync: BoolParam,
+    #[id = "path-bars"]
+    pub length_bars: FloatParam,
+    #[id = "path-seconds"]
+    pub length_seconds: FloatParam,
+    #[persist = "path"]
+    pub path: RwLock<Trajectory>,
+}
+
+impl Default for TrajectoryParams {
+    fn default() -> Self {
+        Self {
+            play: BoolParam::new("Play Path", false),
+            shape: EnumParam::new("Path Shape", PathShape::Spline),
+            mode: EnumParam::new("Path Playback", PlaybackMode::Loop),
+            sync: BoolParam::new("Path Tempo Sync", true),
+            length_bars: FloatParam::new(
+                "Path Length",
+                4.0,
+                FloatRange::Skewed {
+                    min: 0.25,
+                    max: 64.0,
+                    factor: FloatRange::skew_factor(-1.5),
+                },
+            )
+            .with_unit(" bars")
+            .with_step_size(0.25),
+            length_seconds: FloatParam::new(
+                "Path Time",
+                8.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 120.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" s")
+            .with_step_size(0.01),
+            path: RwLock::new(Trajectory::default()),
+        }
+    }
+}
+
+impl TrajectoryParams {
+    pub fn settings(&self) -> TrajectorySettings {
+        TrajectorySettings {
+            shape: self.shape.value(),
+            mode: self.mode.value(),
+            sync: self.sync.value(),
+            length_bars: self.length_bars.value(),
+            length_seconds: self.length_seconds.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    fn settings(shape: PathShape, mode: PlaybackMode) -> TrajectorySettings {
+        TrajectorySettings {
+            shape,
+            mode,
+            sync: false,
+            length_bars: 1.0,
+            length_seconds: 1.0,
+        }
+    }
+
+    /// an l shaped path, one long leg and one short one
+    fn player() -> TrajectoryPlayer {
+        let path = RwLock::new(Trajectory::default());
+        for point in [[0.0, 0.0], [0.75, 0.0], [0.75, 0.25]] {
+            assert!(path.write().push(point));
+        }
+        let mut player = TrajectoryPlayer::default();
+        player.sync_path(&path);
+        player
+    }
+
+    fn run(player: &mut TrajectoryPlayer, settings: &TrajectorySettings) -> Vec<[f32; 2]> {
+        // four samples per run along the path
+        player.begin_block(true, settings, &Grid::default(), 4.0);
+        (0..9)
+            .map(|_| player.next_point(settings).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_polyline_moves_by_distance() {
+        let mut player = player();
+        let points = run(
+            &mut player,
+            &settings(PathShape::Polyline, PlaybackMode::Loop),
+        );
+        assert_eq!(points[1], [0.25, 0.0]);
+        assert_eq!(points[2], [0.5, 0.0]);
+        assert_eq!(points[3], [0.75, 0.0]);
+        // loops straight back to the start
+        assert_eq!(points[4], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_playback_modes() {
+        let mut player = player();
+        let points = run(
+            &mut player,
+            &settings(PathShape::Polyline, PlaybackMode::PingPong),
+        );
+        assert_eq!(points[4], [0.75, 0.25]);
+        assert_eq!(points[5], [0.75, 0.0]);
+        assert_eq!(points[8], [0.0, 0.0]);
+
+        let mut player = self::player();
+        let points = run(
+            &mut player,
+            &settings(PathShape::Polyline, PlaybackMode::OneShot),
+        );
+        assert!(points[4..].iter().all(|point| *point == [0.75, 0.25]));
+
+        // switching off hands the cursor back
+        player.begin_block(
+            false,
+            &settings(PathShape::Polyline, PlaybackMode::OneShot),
+            &Grid::default(),
+            4.0,
+        );
+        assert_eq!(
+            player.next_point(&settings(PathShape::Polyline, PlaybackMode::OneShot)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_spline_passes_through_points() {
+        let points = [[0.0, 0.0], [0.5, 1.0], [1.0, 0.0]];
+        let mut lengths = Vec::new();
+        path_lengths(&points, &mut lengths);
+        assert_approx_eq!(f32, lengths[2], 2.0 * 1.25f32.sqrt());
+
+        let middle = path_point(&points, &lengths, 0.5, PathShape::Spline);
+        assert_approx_eq!(f32, middle[0], 0.5, epsilon = 0.0001);
+        assert_approx_eq!(f32, middle[1], 1.0, epsilon = 0.0001);
+        // the spline bulges past the straight line
+        let quarter = path_point(&points, &lengths, 0.25, PathShape::Spline);
+        assert!(quarter[1] > 0.5);
+    }
+
+    #[test]
+    fn test_full_path() {
+        let mut path = Trajectory::default();
+        for _ in 0..MAX_POINTS {
+            assert!(path.push([2.0, -1.0]));
+        }
+        assert!(!path.push([0.5, 0.5]));
+        assert_eq!(path.points()[0], [1.0, 0.0]);
+    }
+}
@@ -0,0 +1,528 @@
+use nih_plug::prelude::*;
+
+/// how many bars make up a phrase
+const BARS_PER_PHRASE: f64 = 4.0;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantise {
+    #[id = "off"]
+    #[name = "Immediately"]
+    Off,
+    #[id = "beat"]
+    #[name = "Next Beat"]
+    Beat,
+    #[id = "bar"]
+    #[name = "Next Bar"]
+    Bar,
+    #[id = "phrase"]
+    #[name = "Next Phrase"]
+    Phrase,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionDirection {
+    /// towards the main input at 1
+    #[id = "a-to-b"]
+    #[name = "To Main"]
+    ToMain,
+    /// towards the sidechain at 0
+    #[id = "b-to-a"]
+    #[name = "To Side"]
+    ToSide,
+    /// goes to whichever end is further away
+    #[id = "toggle"]
+    #[name = "Toggle"]
+    Toggle,
+}
+
+/// what the tempo synced transition length is counted in
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    #[id = "beats"]
+    #[name = "Beats"]
+    Beats,
+    /// follows the time signature
+    #[id = "bars"]
+    #[name = "Bars"]
+    Bars,
+}
+
+/// how the fader moves over the length of the transition
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionShape {
+    #[id = "linear"]
+    #[name = "Linear"]
+    Linear,
+    #[id = "smooth"]
+    #[name = "Smooth"]
+    Smooth,
+    #[id = "ease-in"]
+    #[name = "Ease In"]
+    EaseIn,
+    #[id = "ease-out"]
+    #[name = "Ease Out"]
+    EaseOut,
+}
+
+impl TransitionShape {
+    /// maps the time through the transition to how far the fader has moved, both 0 to 1
+    pub fn apply(self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            TransitionShape::Linear => progress,
+            TransitionShape::Smooth => progress * progress * (3.0 - 2.0 * progress),
+            TransitionShape::EaseIn => progress * progress,
+            TransitionShape::EaseOut => 1.0 - (1.0 - progress) * (1.0 - progress),
+        }
+    }
+}
+
+/// the part of the host transport the transitions care about
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Grid {
+    pub tempo: Option<f64>,
+    pub pos_beats: Option<f64>,
+    pub bar_start_pos_beats: Option<f64>,
+    /// in quarter notes, like the positions
+    pub beats_per_bar: f64,
+    pub playing: bool,
+}
+
+impl Grid {
+    pub fn from_transport(transport: &Transport) -> Self {
+        let beats_per_bar = match (transport.time_sig_numerator, transport.time_sig_denominator) {
+            (Some(numerator), Some(denominator)) if denominator > 0 => {
+                numerator as f64 * 4.0 / denominator as f64
+            }
+            _ => 4.0,
+        };
+        Self {
+            tempo: transport.tempo,
+            pos_beats: transport.pos_beats(),
+            bar_start_pos_beats: transport.bar_start_pos_beats(),
+            beats_per_bar,
+            playing: transport.playing,
+        }
+    }
+
+    /// samples until the next boundary, zero when there is no usable grid
+    fn samples_until(&self, quantise: Quantise, sample_rate: f32) -> f32 {
+        let (Some(tempo), Some(position), true) = (self.tempo, self.pos_beats, self.playing) else {
+            return 0.0;
+        };
+        let (interval, origin) = match quantise {
+            Quantise::Off => return 0.0,
+            Quantise::Beat => (1.0, 0.0),
+            Quantise::Bar => (
+                self.beats_per_bar,
+                self.bar_start_pos_beats.unwrap_or_default(),
+            ),
+            Quantise::Phrase => (self.beats_per_bar * BARS_PER_PHRASE, 0.0),
+        };
+        if interval <= 0.0 || tempo <= 0.0 {
+            return 0.0;
+        }
+
+        let into_interval = (position - origin).rem_euclid(interval);
+        // right on the boundary counts as already there
+        let beats = match into_interval < 1e-6 {
+            true => 0.0,
+            false => interval - into_interval,
+        };
+        (beats * 60.0 / tempo * sample_rate as f64) as f32
+    }
+
+    /// the transition length in samples, in beats or bars with a tempo and in seconds without one
+    fn length_samples(&self, settings: &TransitionSettings, sample_rate: f32) -> f32 {
+        let beats = match settings.length_unit {
+            LengthUnit::Beats => settings.length as f64,
+            LengthUnit::Bars => settings.length as f64 * self.beats_per_bar,
+        };
+        let seconds = match self.tempo {
+            Some(tempo) if tempo > 0.0 => beats * 60.0 / tempo,
+            _ => settings.length_seconds as f64,
+        };
+        (seconds * sample_rate as f64).max(1.0) as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransitionSettings {
+    pub quantise: Quantise,
+    /// in `length_unit`, used with a tempo
+    pub length: f32,
+    pub length_unit: LengthUnit,
+    pub length_seconds: f32,
+    pub shape: TransitionShape,
+    pub direction: TransitionDirection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// the fader param is in charge
+    Idle,
+    /// waiting for the next grid boundary, `resting` is where the transition was held before
+    Waiting {
+        samples_left: f32,
+        from: f32,
+        to: f32,
+        length: f32,
+        resting: Option<f32>,
+    },
+    Moving {
+        from: f32,
+        to: f32,
+        elapsed: f32,
+        length: f32,
+    },
+    /// done, stays at the end until the fader gets touched
+    Holding(f32),
+}
+
+/**
+moves the fader from one end to the other over a set length once it is triggered,
+optionally starting on the next beat, bar or phrase.
+triggering again while it is waiting or moving turns it around.
+
+while it is running or holding its output replaces the fader position,
+moving the fader param hands control back to the param
+ */
+#[derive(Debug, Clone)]
+pub struct Transition {
+    state: State,
+    shape: TransitionShape,
+    /// the go button from the last update, a toggle button triggers on every change
+    last_go: Option<bool>,
+    /// the fader param value when the transition was triggered
+    fader: f32,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            state: State::Idle,
+            shape: TransitionShape::Linear,
+            last_go: None,
+            fader: 0.0,
+        }
+    }
+}
+
+impl Transition {
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+    }
+
+    /// whether the transition is in charge of the fader right now
+    pub fn is_active(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    /**
+    call once per buffer with the state of the go button and the unsmoothed fader value.
+    every flip of go triggers, so each click on a toggle button counts.
+    moving the fader away from where it was when go was pressed gives control back to the fader
+     */
+    pub fn update(
+        &mut self,
+        go: bool,
+        fader: f32,
+        settings: &TransitionSettings,
+        grid: &Grid,
+        sample_rate: f32,
+    ) {
+        if self.is_active() && (fader - self.fader).abs() > f32::EPSILON {
+            self.state = State::Idle;
+        }
+
+        // the first update only learns where the button is, so loading a preset doesn't trigger
+        if self.last_go.is_some_and(|last_go| last_go != go) {
+            self.fader = fader;
+            self.trigger(fader, settings, grid, sample_rate);
+        }
+        self.last_go = Some(go);
+    }
+
+    fn trigger(
+        &mut self,
+        fader: f32,
+        settings: &TransitionSettings,
+        grid: &Grid,
+        sample_rate: f32,
+    ) {
+        self.shape = settings.shape;
+        let start = |from: f32, resting: Option<f32>| {
+            let to = match settings.direction {
+                TransitionDirection::ToMain => 1.0,
+                TransitionDirection::ToSide => 0.0,
+                TransitionDirection::Toggle => match from < 0.5 {
+                    true => 1.0,
+                    false => 0.0,
+                },
+            };
+            State::Waiting {
+                samples_left: grid.samples_until(settings.quantise, sample_rate),
+                from,
+                to,
+                length: grid.length_samples(settings, sample_rate),
+                resting,
+            }
+        };
+
+        self.state = match self.state {
+            State::Idle => start(fader, None),
+            State::Holding(position) => start(position, Some(position)),
+            // it hasn't moved yet, so turning around just means not going
+            State::Waiting { resting, .. } => match resting {
+                Some(position) => State::Holding(position),
+                None => State::Idle,
+            },
+            // heads back to where it came from, taking as long as it took to get here
+            State::Moving {
+                from,
+                to,
+                elapsed,
+                length,
+            } => State::Moving {
+                from: from + (to - from) * self.shape.apply(elapsed / length),
+                to: from,
+                elapsed: 0.0,
+                length: elapsed.max(1.0),
+            },
+        };
+    }
+
+    /// advances by one sample, returns the position to use instead of the fader if there is one
+    pub fn next_position(&mut self) -> Option<f32> {
+        match &mut self.state {
+            State::Idle => None,
+            State::Holding(position) => Some(*position),
+            State::Waiting {
+                samples_left,
+                from,
+                to,
+                length,
+                ..
+            } => {
+                if *samples_left >= 1.0 {
+                    *samples_left -= 1.0;
+                    return Some(*from);
+                }
+                self.state = State::Moving {
+                    from: *from,
+                    to: *to,
+                    elapsed: 0.0,
+                    length: *length,
+                };
+                self.next_position()
+            }
+            State::Moving {
+                from,
+                to,
+                elapsed,
+                length,
+            } => {
+                *elapsed += 1.0;
+                let progress = *elapsed / *length;
+                let position = *from + (*to - *from) * self.shape.apply(progress);
+                if progress >= 1.0 {
+                    self.state = State::Holding(*to);
+                }
+                Some(position)
+            }
+        }
+    }
+}
+
+#[derive(Params)]
+pub struct TransitionParams {
+    #[id = "go"]
+    pub go: BoolParam,
+    #[id = "quantise"]
+    pub quantise: EnumParam<Quantise>,
+    #[id = "length"]
+    pub length: FloatParam,
+    #[id = "length-unit"]
+    pub length_unit: EnumParam<LengthUnit>,
+    #[id = "length-seconds"]
+    pub length_seconds: FloatParam,
+    #[id = "shape"]
+    pub shape: EnumParam<TransitionShape>,
+    #[id = "direction"]
+    pub direction: EnumParam<TransitionDirection>,
+}
+
+impl Default for TransitionParams {
+    fn default() -> Self {
+        Self {
+            go: BoolParam::new("Go", false),
+            quantise: EnumParam::new("Start On", Quantise::Bar),
+            // the unit is its own param, so there is none on the value
+            length: FloatParam::new(
+                "Transition Length",
+                4.0,
+                FloatRange::Skewed {
+                    min: 0.25,
+                    max: 64.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_step_size(0.25),
+            length_unit: EnumParam::new("Transition Length Unit", LengthUnit::Beats),
+            length_seconds: FloatParam::new(
+                "Transition Length Without Tempo",
+                2.0,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 60.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" s")
+            .with_step_size(0.01),
+            shape: EnumParam::new("Transition Shape", TransitionShape::Smooth),
+            direction: EnumParam::new("Direction", TransitionDirection::Toggle),
+        }
+    }
+}
+
+impl TransitionParams {
+    /// reads the current param values
+    pub fn settings(&self) -> TransitionSettings {
+        TransitionSettings {
+            quantise: self.quantise.value(),
+            length: self.length.value(),
+            length_unit: self.length_unit.value(),
+            length_seconds: self.length_seconds.value(),
+            shape: self.shape.value(),
+            direction: self.direction.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn settings(quantise: Quantise) -> TransitionSettings {
+        TransitionSettings {
+            quantise,
+            length: 1.0,
+            length_unit: LengthUnit::Beats,
+            length_seconds: 0.5,
+            shape: TransitionShape::Linear,
+            direction: TransitionDirection::Toggle,
+        }
+    }
+
+    /// 120 bpm, so one beat is 24000 samples
+    fn grid(pos_beats: f64) -> Grid {
+        Grid {
+            tempo: Some(120.0),
+            pos_beats: Some(pos_beats),
+            bar_start_pos_beats: Some((pos_beats / 4.0).floor() * 4.0),
+            beats_per_bar: 4.0,
+            playing: true,
+        }
+    }
+
+    fn run(transition: &mut Transition, samples: usize) -> f32 {
+        (0..samples).fold(0.0, |_, _| transition.next_position().unwrap())
+    }
+
+    /// a transition that just had go pressed once
+    fn pressed(fader: f32, settings: &TransitionSettings, grid: &Grid) -> Transition {
+        let mut transition = Transition::default();
+        transition.update(false, fader, settings, grid, SAMPLE_RATE);
+        transition.update(true, fader, settings, grid, SAMPLE_RATE);
+        transition
+    }
+
+    #[test]
+    fn test_shapes() {
+        for shape in [
+            TransitionShape::Linear,
+            TransitionShape::Smooth,
+            TransitionShape::EaseIn,
+            TransitionShape::EaseOut,
+        ] {
+            assert_eq!(shape.apply(0.0), 0.0);
+            assert_eq!(shape.apply(1.0), 1.0);
+        }
+        assert_eq!(TransitionShape::Smooth.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_waits_for_bar() {
+        // half a beat into the bar, so 3.5 beats to go
+        let mut transition = pressed(0.0, &settings(Quantise::Bar), &grid(0.5));
+        assert_eq!(run(&mut transition, 3 * 24000 + 12000 - 1), 0.0);
+        // halfway through the one beat transition
+        assert_approx_eq!(f32, run(&mut transition, 12001), 0.5, epsilon = 0.001);
+        assert_approx_eq!(f32, run(&mut transition, 24000), 1.0);
+        // holds at the end
+        assert_eq!(run(&mut transition, 100), 1.0);
+    }
+
+    #[test]
+    fn test_length_in_bars() {
+        let settings = TransitionSettings {
+            length_unit: LengthUnit::Bars,
+            ..settings(Quantise::Off)
+        };
+        // one bar of 3/4 is three beats
+        let grid = Grid {
+            beats_per_bar: 3.0,
+            ..grid(0.0)
+        };
+        let mut transition = pressed(0.0, &settings, &grid);
+        assert_approx_eq!(f32, run(&mut transition, 36000), 0.5, epsilon = 0.001);
+        assert_approx_eq!(f32, run(&mut transition, 36000), 1.0);
+    }
+
+    #[test]
+    fn test_no_tempo_uses_seconds() {
+        let mut transition = pressed(0.0, &settings(Quantise::Phrase), &Grid::default());
+        // starts right away and takes half a second
+        assert_approx_eq!(f32, run(&mut transition, 12000), 0.5, epsilon = 0.001);
+        assert_approx_eq!(f32, run(&mut transition, 12000), 1.0);
+    }
+
+    #[test]
+    fn test_first_update_doesnt_trigger() {
+        let mut transition = Transition::default();
+        let settings = settings(Quantise::Off);
+        transition.update(true, 0.0, &settings, &grid(0.0), SAMPLE_RATE);
+        assert_eq!(transition.next_position(), None);
+    }
+
+    #[test]
+    fn test_retrigger_reverses() {
+        let settings = settings(Quantise::Off);
+        let mut transition = pressed(0.0, &settings, &grid(0.0));
+        let halfway = run(&mut transition, 12000);
+        // a toggle button going back off is another press
+        transition.update(false, 0.0, &settings, &grid(0.0), SAMPLE_RATE);
+        // no jump when turning around
+        assert_approx_eq!(
+            f32,
+            transition.next_position().unwrap(),
+            halfway,
+            epsilon = 0.001
+        );
+        assert_approx_eq!(f32, run(&mut transition, 12000), 0.0);
+    }
+
+    #[test]
+    fn test_fader_takes_over() {
+        let settings = settings(Quantise::Off);
+        let mut transition = pressed(0.2, &settings, &grid(0.0));
+        run(&mut transition, 48000);
+        assert!(transition.is_active());
+        transition.update(true, 0.3, &settings, &grid(0.0), SAMPLE_RATE);
+        assert_eq!(transition.next_position(), None);
+    }
+}
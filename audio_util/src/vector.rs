@@ -0,0 +1,281 @@
+use crate::buffer::MAX_SOURCES;
+use nih_plug::prelude::*;
+
+/// a convex cell never has more corners than the square plus one per clip
+const MAX_CORNERS: usize = 4 + 2 * MAX_SOURCES;
+
+/// how the cursor position is turned into source weights
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorWeighting {
+    /// sources get louder the closer the cursor is, fading out at their radius
+    #[id = "inverse-distance"]
+    #[name = "Inverse Distance"]
+    InverseDistance,
+    /// sources get the share of space the cursor would take away from them
+    #[id = "natural-neighbour"]
+    #[name = "Natural Neighbour"]
+    NaturalNeighbour,
+}
+
+/// a source placed somewhere on the vector plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorSource {
+    pub x: f32,
+    pub y: f32,
+    /// the source is silent when the cursor is further away than this
+    pub radius: f32,
+}
+
+impl VectorSource {
+    fn distance(&self, x: f32, y: f32) -> f32 {
+        (self.x - x).hypot(self.y - y)
+    }
+}
+
+/**
+fills `weights` with one gain per source for a cursor at `cursor_x`, `cursor_y`.
+the weights add up to 1 unless the cursor is outside the radius of every source,
+then they are all 0
+ */
+pub fn vector_weights(
+    weighting: VectorWeighting,
+    cursor_x: f32,
+    cursor_y: f32,
+    sources: &[VectorSource],
+    weights: &mut [f32],
+) {
+    debug_assert_eq!(sources.len(), weights.len());
+
+    match weighting {
+        VectorWeighting::InverseDistance => {
+            inverse_distance_weights(cursor_x, cursor_y, sources, weights)
+        }
+        VectorWeighting::NaturalNeighbour => {
+            natural_neighbour_weights(cursor_x, cursor_y, sources, weights)
+        }
+    }
+    normalize(weights);
+}
+
+/// modified shepard weighting, `((radius - distance) / (radius * distance))^2`
+fn inverse_distance_weights(
+    cursor_x: f32,
+    cursor_y: f32,
+    sources: &[VectorSource],
+    weights: &mut [f32],
+) {
+    for (source, weight) in sources.iter().zip(weights.iter_mut()) {
+        let distance = source.distance(cursor_x, cursor_y);
+        *weight = match distance < source.radius {
+            true => ((source.radius - distance) / (source.radius * distance)).powi(2),
+            false => 0.0,
+        };
+    }
+
+    // sitting right on top of a source only plays that one
+    if let Some(index) = weights.iter().position(|weight| weight.is_infinite()) {
+        weights.fill(0.0);
+        weights[index] = 1.0;
+    }
+}
+
+/**
+sibson interpolation over the unit square, worked out from the voronoi cells.
+the cursor gets added to the voronoi diagram of the sources and every source is weighted by
+how much of its cell the cursor takes away. sources outside their radius are left out of the
+tesselation, there can be at most `MAX_SOURCES`
+ */
+fn natural_neighbour_weights(
+    cursor_x: f32,
+    cursor_y: f32,
+    sources: &[VectorSource],
+    weights: &mut [f32],
+) {
+    debug_assert!(sources.len() <= MAX_SOURCES);
+    weights.fill(0.0);
+    let cursor = [cursor_x, cursor_y];
+    let is_active = |source: &VectorSource| source.distance(cursor_x, cursor_y) < source.radius;
+
+    // the cursor is right on top of a source so it can't steal any space
+    if let Some(index) = sources
+        .iter()
+        .position(|source| is_active(source) && source.distance(cursor_x, cursor_y) < 1e-6)
+    {
+        weights[index] = 1.0;
+        return;
+    }
+
+    // everything that is closer to the cursor than to any source
+    let mut cursor_cell = Cell::unit_square();
+    for source in sources.iter().filter(|source| is_active(source)) {
+        cursor_cell.clip_closer_to(cursor, [source.x, source.y]);
+    }
+
+    for (index, source) in sources.iter().enumerate() {
+        if !is_active(source) {
+            continue;
+        }
+        // the part of the cursor's cell that belonged to this source before
+        let mut stolen = cursor_cell.clone();
+        for (other_index, other) in sources.iter().enumerate() {
+            if other_index != index && is_active(other) {
+                stolen.clip_closer_to([source.x, source.y], [other.x, other.y]);
+            }
+        }
+        weights[index] = stolen.area();
+    }
+}
+
+/// a convex polygon that lives on the stack
+#[derive(Debug, Clone)]
+struct Cell {
+    corners: [[f32; 2]; MAX_CORNERS],
+    len: usize,
+}
+
+impl Cell {
+    fn unit_square() -> Self {
+        let mut corners = [[0.0; 2]; MAX_CORNERS];
+        corners[..4].copy_from_slice(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        Self { corners, len: 4 }
+    }
+
+    /// cuts away everything that is closer to `other` than to `point`
+    fn clip_closer_to(&mut self, point: [f32; 2], other: [f32; 2]) {
+        let normal = [other[0] - point[0], other[1] - point[1]];
+        let middle = [(point[0] + other[0]) / 2.0, (point[1] + other[1]) / 2.0];
+        // negative on the side of `point`
+        let side = |corner: [f32; 2]| {
+            (corner[0] - middle[0]) * normal[0] + (corner[1] - middle[1]) * normal[1]
+        };
+
+        let input = self.clone();
+        self.len = 0;
+        for index in 0..input.len {
+            let corner = input.corners[index];
+            let next = input.corners[(index + 1) % input.len];
+            let (corner_side, next_side) = (side(corner), side(next));
+
+            if corner_side <= 0.0 {
+                self.push(corner);
+            }
+            if (corner_side <= 0.0) != (next_side <= 0.0) {
+                let amount = corner_side / (corner_side - next_side);
+                self.push([
+                    corner[0] + (next[0] - corner[0]) * amount,
+                    corner[1] + (next[1] - corner[1]) * amount,
+                ]);
+            }
+        }
+    }
+
+    fn push(&mut self, corner: [f32; 2]) {
+        if self.len < MAX_CORNERS {
+            self.corners[self.len] = corner;
+            self.len += 1;
+        }
+    }
+
+    /// shoelace formula
+    fn area(&self) -> f32 {
+        let corners = &self.corners[..self.len];
+        let twice_area: f32 = corners
+            .iter()
+            .zip(corners.iter().cycle().skip(1))
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum();
+        twice_area.abs() / 2.0
+    }
+}
+
+fn normalize(weights: &mut [f32]) {
+    let sum: f32 = weights.iter().sum();
+    if sum > 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= sum);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    fn diamond() -> [VectorSource; 4] {
+        [(0.5, 0.1), (0.9, 0.5), (0.5, 0.9), (0.1, 0.5)].map(|(x, y)| VectorSource {
+            x,
+            y,
+            radius: 1.0,
+        })
+    }
+
+    #[test]
+    fn test_weights_sum_to_one() {
+        for weighting in [
+            VectorWeighting::InverseDistance,
+            VectorWeighting::NaturalNeighbour,
+        ] {
+            let mut weights = [0.0; 4];
+            vector_weights(weighting, 0.3, 0.6, &diamond(), &mut weights);
+            assert_approx_eq!(f32, weights.iter().sum(), 1.0, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_cursor_on_source() {
+        for weighting in [
+            VectorWeighting::InverseDistance,
+            VectorWeighting::NaturalNeighbour,
+        ] {
+            let mut weights = [0.0; 4];
+            vector_weights(weighting, 0.9, 0.5, &diamond(), &mut weights);
+            assert_eq!(weights, [0.0, 1.0, 0.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn test_center_is_even() {
+        for weighting in [
+            VectorWeighting::InverseDistance,
+            VectorWeighting::NaturalNeighbour,
+        ] {
+            let mut weights = [0.0; 4];
+            vector_weights(weighting, 0.5, 0.5, &diamond(), &mut weights);
+            for weight in weights {
+                assert_approx_eq!(f32, weight, 0.25, epsilon = 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_natural_neighbour_areas() {
+        let sources = [(0.25, 0.5), (0.75, 0.5)].map(|(x, y)| VectorSource { x, y, radius: 1.0 });
+        // the cursor's cell runs from x 0.325 to 0.575, split at 0.5 between the sources
+        let mut weights = [0.0; 2];
+        vector_weights(
+            VectorWeighting::NaturalNeighbour,
+            0.4,
+            0.5,
+            &sources,
+            &mut weights,
+        );
+        assert_approx_eq!(f32, weights[0], 0.7, epsilon = 0.0001);
+        assert_approx_eq!(f32, weights[1], 0.3, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_outside_radius() {
+        let sources = diamond().map(|source| VectorSource {
+            radius: 0.1,
+            ..source
+        });
+        let mut weights = [1.0; 4];
+        vector_weights(
+            VectorWeighting::InverseDistance,
+            0.5,
+            0.5,
+            &sources,
+            &mut weights,
+        );
+        assert_eq!(weights, [0.0; 4]);
+    }
+}
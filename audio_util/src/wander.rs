@@ -0,0 +1,333 @@
+use crate::{
+    midi::MAX_AXES,
+    random::{self, Xorshift},
+};
+use nih_plug::prelude::*;
+
+/// how far along the noise one wander step moves, smaller turns more slowly
+const NOISE_SPEED: f64 = 0.1;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WanderMode {
+    /// every step goes off in a new random direction
+    #[id = "brownian"]
+    #[name = "Brownian"]
+    Brownian,
+    /// the direction drifts slowly, so it wanders in long curves
+    #[id = "noise"]
+    #[name = "Perlin Noise"]
+    Noise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WanderSettings {
+    pub mode: WanderMode,
+    pub seed: u32,
+    /// steps per second
+    pub rate_hz: f32,
+    /// how far one step can go
+    pub step: f32,
+    /// how much of the axis it can wander across, centered on the axis position
+    pub bounds: f32,
+    /// how much of the way back to the axis position every step goes
+    pub attraction: f32,
+}
+
+impl Default for WanderSettings {
+    fn default() -> Self {
+        Self {
+            mode: WanderMode::Brownian,
+            seed: 1,
+            rate_hz: 1.0,
+            step: 0.1,
+            bounds: 1.0,
+            attraction: 0.1,
+        }
+    }
+}
+
+/**
+wanders the axes around on its own, gliding from one random step to the next.
+everything comes from the seed, so the same seed always wanders the same way
+from a reset, which keeps offline renders identical.
+
+call `set_settings` once per buffer and `next` for every sample
+ */
+#[derive(Debug, Clone)]
+pub struct Wander {
+    settings: WanderSettings,
+    random: Xorshift,
+    /// the offsets from the axis positions at the start and the end of the current step
+    from: [f32; MAX_AXES],
+    to: [f32; MAX_AXES],
+    /// 0 to 1 through the current step
+    phase: f32,
+    increment: f32,
+    /// steps since the reset, where the noise gets read from
+    steps: u64,
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        let settings = WanderSettings::default();
+        Self {
+            settings,
+            random: Xorshift::new(settings.seed),
+            from: [0.0; MAX_AXES],
+            to: [0.0; MAX_AXES],
+            phase: 1.0,
+            increment: 0.0,
+            steps: 0,
+        }
+    }
+}
+
+impl Wander {
+    /// starts again from the axis positions, the next steps will be the same as last time
+    pub fn reset(&mut self) {
+        self.random = Xorshift::new(self.settings.seed);
+        self.from = [0.0; MAX_AXES];
+        self.to = [0.0; MAX_AXES];
+        // takes the first step straight away
+        self.phase = 1.0;
+        self.steps = 0;
+    }
+
+    pub fn set_settings(&mut self, settings: &WanderSettings, sample_rate: f32) {
+        let new_seed = settings.seed != self.settings.seed;
+        self.settings = *settings;
+        self.increment = settings.rate_hz / sample_rate.max(1.0);
+        if new_seed {
+            self.reset();
+        }
+    }
+
+    /// advances by one sample and moves every one of `positions` by its wandered offset
+    pub fn next(&mut self, positions: &mut [f32]) {
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.step();
+        }
+
+        let amount = self.phase * self.phase * (3.0 - 2.0 * self.phase);
+        for ((position, from), to) in positions.iter_mut().zip(self.from).zip(self.to) {
+            *position = (*position + from + (to - from) * amount).clamp(0.0, 1.0);
+        }
+        self.phase += self.increment;
+    }
+
+    fn step(&mut self) {
+        let settings = self.settings;
+        self.from = self.to;
+        for axis in 0..MAX_AXES {
+            let direction = match settings.mode {
+                WanderMode::Brownian => self.random.next_bipolar(),
+                WanderMode::Noise => random::gradient_noise(
+                    settings.seed.wrapping_add(axis as u32),
+                    self.steps as f64 * NOISE_SPEED,
+                ),
+            };
+            let offset = self.from[axis] * (1.0 - settings.attraction) + settings.step * direction;
+            self.to[axis] = reflect(offset, settings.bounds * 0.5);
+        }
+        self.steps += 1;
+    }
+}
+
+/// folds `offset` back into -`limit` to `limit`, like bouncing off the edges
+fn reflect(offset: f32, limit: f32) -> f32 {
+    if limit <= 0.0 {
+        return 0.0;
+    }
+    let folded = (offset + limit).rem_euclid(4.0 * limit);
+    match folded <= 2.0 * limit {
+        true => folded - limit,
+        false => 3.0 * limit - folded,
+    }
+}
+
+#[derive(Params)]
+pub struct WanderParams {
+    #[id = "wander"]
+    pub enabled: BoolParam,
+    #[id = "wander-mode"]
+    pub mode: EnumParam<WanderMode>,
+    /// changing this starts the wander again from the axis positions
+    #[id = "wander-seed"]
+    pub seed: IntParam,
+    #[id = "wander-rate"]
+    pub rate: FloatParam,
+    #[id = "wander-step"]
+    pub step: FloatParam,
+    #[id = "wander-bounds"]
+    pub bounds: FloatParam,
+    #[id = "wander-attraction"]
+    pub attraction: FloatParam,
+}
+
+impl Default for WanderParams {
+    fn default() -> Self {
+        let defaults = WanderSettings::default();
+
+        Self {
+            enabled: BoolParam::new("Wander", false),
+            mode: EnumParam::new("Wander Mode", defaults.mode),
+            seed: IntParam::new(
+                "Wander Seed",
+                defaults.seed as i32,
+                IntRange::Linear { min: 1, max: 9999 },
+            ),
+            rate: FloatParam::new(
+                "Wander Rate",
+                defaults.rate_hz,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(0.01),
+            step: FloatParam::new(
+                "Wander Step",
+                defaults.step,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 0.5,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_percentage(1))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            bounds: FloatParam::new(
+                "Wander Bounds",
+                defaults.bounds,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            attraction: FloatParam::new(
+                "Wander Attraction",
+                defaults.attraction,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+        }
+    }
+}
+
+impl WanderParams {
+    pub fn settings(&self) -> WanderSettings {
+        WanderSettings {
+            mode: self.mode.value(),
+            seed: self.seed.value() as u32,
+            rate_hz: self.rate.value(),
+            step: self.step.value(),
+            bounds: self.bounds.value(),
+            attraction: self.attraction.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    /// ten samples per step
+    fn wander(settings: WanderSettings) -> Wander {
+        let mut wander = Wander::default();
+        wander.set_settings(&settings, settings.rate_hz * 10.0);
+        wander.reset();
+        wander
+    }
+
+    fn run(wander: &mut Wander, samples: usize) -> Vec<[f32; MAX_AXES]> {
+        (0..samples)
+            .map(|_| {
+                let mut positions = [0.5; MAX_AXES];
+                wander.next(&mut positions);
+                positions
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded() {
+        for mode in [WanderMode::Brownian, WanderMode::Noise] {
+            let settings = WanderSettings {
+                mode,
+                seed: 42,
+                ..WanderSettings::default()
+            };
+            let first = run(&mut wander(settings), 1000);
+            assert_eq!(run(&mut wander(settings), 1000), first);
+
+            // a reset goes back to the start
+            let mut again = wander(settings);
+            run(&mut again, 123);
+            again.reset();
+            assert_eq!(run(&mut again, 1000), first);
+
+            let other = run(
+                &mut wander(WanderSettings {
+                    seed: 43,
+                    ..settings
+                }),
+                1000,
+            );
+            assert_ne!(other, first);
+        }
+    }
+
+    #[test]
+    fn test_stays_in_bounds() {
+        for mode in [WanderMode::Brownian, WanderMode::Noise] {
+            let settings = WanderSettings {
+                mode,
+                step: 0.5,
+                bounds: 0.2,
+                attraction: 0.0,
+                ..WanderSettings::default()
+            };
+            let positions = run(&mut wander(settings), 10000);
+            assert!(positions
+                .iter()
+                .flatten()
+                .all(|position| (0.4..=0.6).contains(position)));
+            // it does get around though
+            assert!(positions.iter().any(|position| position[0] > 0.55));
+            assert!(positions.iter().any(|position| position[0] < 0.45));
+        }
+    }
+
+    #[test]
+    fn test_attraction_pulls_back() {
+        let mut wander = wander(WanderSettings::default());
+        run(&mut wander, 100);
+        wander.set_settings(
+            &WanderSettings {
+                step: 0.0,
+                attraction: 1.0,
+                ..WanderSettings::default()
+            },
+            10.0,
+        );
+        let positions = run(&mut wander, 40);
+        assert_eq!(positions[39], [0.5; MAX_AXES]);
+    }
+
+    #[test]
+    fn test_reflect() {
+        assert_approx_eq!(f32, reflect(0.05, 0.1), 0.05);
+        assert_approx_eq!(f32, reflect(0.15, 0.1), 0.05);
+        assert_approx_eq!(f32, reflect(-0.15, 0.1), -0.05);
+        assert_approx_eq!(f32, reflect(0.25, 0.1), -0.05);
+        assert_approx_eq!(f32, reflect(0.45, 0.1), 0.05);
+        assert_eq!(reflect(0.3, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,277 @@
+use audio_util::{
+    buffer,
+    crossfade::CrossfadeParams,
+    crossover::{ThreeBandCrossover, BANDS},
+    midi::{MidiControl, MidiParams},
+};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+const CHANNELS: usize = 2;
+const BAND_NAMES: [&str; BANDS] = ["Low", "Mid", "High"];
+
+struct BandFade {
+    params: Arc<BandFadeParams>,
+    sample_rate: f32,
+    /// one crossover per channel for the main input and for the sidechain
+    main_crossovers: [ThreeBandCrossover; CHANNELS],
+    side_crossovers: [ThreeBandCrossover; CHANNELS],
+    /// the crossover frequencies the filters are set to right now
+    frequencies: (f32, f32),
+    midi: MidiControl,
+}
+
+impl Default for BandFade {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(BandFadeParams::default()),
+            sample_rate: 44100.0,
+            main_crossovers: Default::default(),
+            side_crossovers: Default::default(),
+            frequencies: (0.0, 0.0),
+            midi: MidiControl::default(),
+        }
+    }
+}
+
+#[derive(Params)]
+struct BandFadeParams {
+    #[id = "low-crossover"]
+    pub low_crossover: FloatParam,
+    #[id = "high-crossover"]
+    pub high_crossover: FloatParam,
+    #[nested(array, group = "Band")]
+    pub bands: [BandParams; BANDS],
+    /// the x, y and z ccs move the low, mid and high bands
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+}
+
+impl Default for BandFadeParams {
+    fn default() -> Self {
+        Self {
+            low_crossover: frequency_param("Low Crossover", 250.0, 20.0, 2000.0),
+            high_crossover: frequency_param("High Crossover", 2500.0, 200.0, 20000.0),
+            bands: std::array::from_fn(BandParams::new),
+            midi: MidiParams::new(&BAND_NAMES),
+        }
+    }
+}
+
+fn frequency_param(name: &str, default: f32, min: f32, max: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min,
+            max,
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    // gliding the frequencies keeps the filters from clicking when they move
+    .with_smoother(SmoothingStyle::Logarithmic(20.0))
+    .with_unit(" Hz")
+    .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+    .with_string_to_value(formatters::s2v_f32_hz_then_khz())
+}
+
+/// the fade position and curve of one band
+#[derive(Params)]
+struct BandParams {
+    #[id = "X"]
+    pub position: FloatParam,
+    #[nested(group = "Curve")]
+    pub curve: CrossfadeParams,
+}
+
+impl BandParams {
+    fn new(index: usize) -> Self {
+        let name = BAND_NAMES[index];
+
+        Self {
+            position: FloatParam::new(
+                format!("{name} X Mix"),
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_step_size(0.0001),
+            curve: CrossfadeParams::named(&format!("{name} ")),
+        }
+    }
+}
+
+impl BandFade {
+    /// only touches the filters when a crossover frequency actually moved
+    fn update_crossovers(&mut self, frequencies: (f32, f32)) {
+        if frequencies == self.frequencies {
+            return;
+        }
+        self.frequencies = frequencies;
+
+        for crossover in self
+            .main_crossovers
+            .iter_mut()
+            .chain(self.side_crossovers.iter_mut())
+        {
+            crossover.set_frequencies(self.sample_rate, frequencies.0, frequencies.1);
+        }
+    }
+}
+
+impl Plugin for BandFade {
+    // metadata
+    const NAME: &'static str = "Band Fader";
+    const VENDOR: &'static str = "zoe bat";
+    const URL: &'static str = "https://zoe.kittycat.homes";
+    const EMAIL: &'static str = "zoe@kittycat.homes";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        // stereo
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+
+            names: PortNames {
+                layout: Some("Stereo"),
+                main_input: Some("Main"),
+                main_output: Some("Out"),
+                aux_inputs: &["Sidechain"],
+                aux_outputs: &[],
+            },
+        },
+    ];
+
+    type SysExMessage = ();
+
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.sample_rate = buffer_config.sample_rate;
+        // forces the filters to be set up for the new sample rate
+        self.frequencies = (0.0, 0.0);
+        self.update_crossovers((
+            self.params.low_crossover.value(),
+            self.params.high_crossover.value(),
+        ));
+        self.midi.load(&self.params.midi.notes);
+        true
+    }
+
+    fn reset(&mut self) {
+        for crossover in self
+            .main_crossovers
+            .iter_mut()
+            .chain(self.side_crossovers.iter_mut())
+        {
+            crossover.reset();
+        }
+        self.midi.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let crossfades = self
+            .params
+            .bands
+            .each_ref()
+            .map(|band| band.curve.crossfade());
+
+        self.midi.follow_faders(
+            &self
+                .params
+                .bands
+                .each_ref()
+                .map(|band| band.position.value()),
+        );
+        let midi_settings = self.params.midi.settings(self.sample_rate);
+        let mut next_event = context.next_event();
+
+        for (sample_index, channel_samples) in buffer.iter_samples().enumerate() {
+            self.midi.handle_events(
+                sample_index as u32,
+                &mut next_event,
+                || context.next_event(),
+                &midi_settings,
+            );
+            self.update_crossovers((
+                self.params.low_crossover.smoothed.next(),
+                self.params.high_crossover.smoothed.next(),
+            ));
+            let mut positions = self
+                .params
+                .bands
+                .each_ref()
+                .map(|band| band.position.smoothed.next());
+            self.midi.next(&mut positions);
+            let gains: [(f32, f32); BANDS] =
+                std::array::from_fn(|band| crossfades[band].gains(positions[band]));
+
+            for (channel_index, sample) in channel_samples.into_iter().enumerate().take(CHANNELS) {
+                let side = buffer::get_sample_at_position(channel_index, sample_index, aux);
+                let main_bands = self.main_crossovers[channel_index].process(*sample);
+                let side_bands = self.side_crossovers[channel_index].process(side);
+
+                // with the same position on every band this is the same as fading
+                // the allpassed inputs, so the sum stays flat
+                *sample = gains
+                    .iter()
+                    .zip(main_bands.iter().zip(&side_bands))
+                    .map(|((main_gain, side_gain), (main, side))| {
+                        main * main_gain + side * side_gain
+                    })
+                    .sum();
+            }
+        }
+
+        self.midi.publish(&self.params.midi.notes);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for BandFade {
+    const CLAP_ID: &'static str = "fruitsuite.band_fader";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("three band crossfader with its own fade position for every band");
+    const CLAP_MANUAL_URL: Option<&'static str> = None;
+    const CLAP_SUPPORT_URL: Option<&'static str> = Some("https://zoe.kittycat.homes");
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::Stereo,
+        ClapFeature::Utility,
+        ClapFeature::Custom("Crossfade"),
+        ClapFeature::AudioEffect,
+    ];
+}
+
+impl Vst3Plugin for BandFade {
+    const VST3_CLASS_ID: [u8; 16] = *b"fruit.BandFader0";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
+        Vst3SubCategory::Fx,
+        Vst3SubCategory::Stereo,
+        Vst3SubCategory::Tools,
+    ];
+}
+
+nih_export_clap!(BandFade);
+nih_export_vst3!(BandFade);
@@ -13,6 +13,12 @@ subprocess.run(
         "xyz_fader",
         "-p",
         "crossfader_gui",
+        "-p",
+        "vector_fader",
+        "-p",
+        "band_fader",
+        "-p",
+        "spectral_fader",
         "--release"
     ]
 )
@@ -1,45 +1,161 @@
+use atomic_float::AtomicF32;
 use nih_plug::prelude::Editor;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
-use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
-use std::sync::Arc;
+use nih_plug_vizia::{create_vizia_editor, ViziaState, ViziaTheming};
+use std::sync::{atomic::Ordering, Arc};
+use style::widgets::{Crossfader, CrossfaderExt, CurvePlot};
 
 use crate::XFaderParams;
 
 #[derive(Lens)]
 struct Data {
     params: Arc<XFaderParams>,
+    law: Arc<AtomicF32>,
+    position: Arc<AtomicF32>,
 }
 
 impl Model for Data {}
 
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    return ViziaState::new(|| (200, 150));
+    return ViziaState::new(|| (440, 660));
 }
 
 pub(crate) fn create(
     params: Arc<XFaderParams>,
+    law: Arc<AtomicF32>,
+    position: Arc<AtomicF32>,
     editor_state: Arc<ViziaState>,
 ) -> Option<Box<dyn Editor>> {
     create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
-        assets::register_noto_sans_light(cx);
-        assets::register_noto_sans_thin(cx);
+        style::theme::install(cx, &style::color::DARK);
 
         Data {
             params: params.clone(),
+            law: law.clone(),
+            position: position.clone(),
         }
         .build(cx);
 
         VStack::new(cx, |cx| {
-            Label::new(cx, "X¹Fader")
-                .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
-                .font_size(style::font::size::XL);
+            Label::new(cx, "X¹Fader").class("title");
 
-            Label::new(cx, "Fade Strength");
-            ParamSlider::new(cx, Data::params, |params| &params.fade_strength);
+            // two columns keep the window short enough for a 1080p screen
+            HStack::new(cx, |cx| {
+                VStack::new(cx, |cx| {
+                    Crossfader::new(
+                        cx,
+                        Data::params,
+                        |params| &params.fade_strength,
+                        ["Side", "Main"],
+                    )
+                    .live_position(position.clone())
+                    .width(Pixels(180.0))
+                    .height(Pixels(40.0))
+                    .bind(Data::position.map(load), |handle, _| {
+                        handle.cx.needs_redraw()
+                    });
+                    curve_plot(cx, params.clone(), law.clone(), position.clone());
+
+                    Label::new(cx, "Curve").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.curve.curve);
+                    Label::new(cx, "Cut Sharpness");
+                    ParamSlider::new(cx, Data::params, |params| &params.curve.sharpness);
+                    ParamButton::new(cx, Data::params, |params| &params.curve.reverse);
+                    ParamButton::new(cx, Data::params, |params| &params.adaptive);
+                    Label::new(
+                        cx,
+                        Data::law.map(|law| law_text(law.load(Ordering::Relaxed))),
+                    )
+                    .class("value");
+
+                    Label::new(cx, "Smoothing").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.smoothing.mode);
+                    ParamSlider::new(cx, Data::params, |params| &params.smoothing.time);
+                    ParamSlider::new(cx, Data::params, |params| &params.smoothing.max_speed);
+                })
+                .row_between(Pixels(style::spacing::SM))
+                .width(Auto)
+                .height(Auto);
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Transition").class("section");
+                    ParamButton::new(cx, Data::params, |params| &params.transition.go);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.quantise);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.length);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.length_unit);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.length_seconds);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.shape);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.direction);
+
+                    Label::new(cx, "Auto Fade").class("section");
+                    ParamButton::new(cx, Data::params, |params| &params.auto_fade.enabled);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.input);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.detector);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.attack);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.release);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.threshold);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.range);
+                    ParamButton::new(cx, Data::params, |params| &params.auto_fade.invert);
+
+                    Label::new(cx, "Gesture").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.gesture.mode);
+                    ParamSlider::new(cx, Data::params, |params| &params.gesture.bars);
+                    ParamButton::new(cx, Data::params, |params| &params.gesture.clear);
+                })
+                .row_between(Pixels(style::spacing::SM))
+                .width(Auto)
+                .height(Auto);
+            })
+            .col_between(Pixels(style::spacing::XXL))
+            .width(Auto)
+            .height(Auto);
         })
-        .row_between(Pixels(0.0))
+        .row_between(Pixels(style::spacing::SM))
+        .child_top(Pixels(style::spacing::XL))
+        .child_bottom(Pixels(style::spacing::XL))
         .child_left(Stretch(1.0))
         .child_right(Stretch(1.0));
     })
 }
+
+/// the gain of both inputs across the fader, using the adaptive law when that is on
+fn curve_plot(
+    cx: &mut Context,
+    params: Arc<XFaderParams>,
+    law: Arc<AtomicF32>,
+    position: Arc<AtomicF32>,
+) {
+    let gains = move |position| {
+        let crossfade = params.curve.crossfade();
+        let law = law.load(Ordering::Relaxed);
+        match law < 0.0 {
+            true => crossfade.gains(position),
+            false => crossfade.adaptive_gains(position, law),
+        }
+    };
+    CurvePlot::new(cx, gains, position)
+        .width(Pixels(180.0))
+        .height(Pixels(80.0))
+        // the curve params, the law and the position all change what gets drawn
+        .bind(
+            Data::params.map(|params| params.curve.crossfade()),
+            |handle, _| handle.cx.needs_redraw(),
+        )
+        .bind(Data::law.map(load), |handle, _| handle.cx.needs_redraw())
+        .bind(Data::position.map(load), |handle, _| {
+            handle.cx.needs_redraw()
+        });
+}
+
+fn load(value: &Arc<AtomicF32>) -> f32 {
+    value.load(Ordering::Relaxed)
+}
+
+/// describes the law the adaptive mode is using
+fn law_text(law: f32) -> String {
+    match law < 0.0 {
+        true => String::from("Law: Curve"),
+        false => format!("Law: {:.0}% Constant Gain", law * 100.0),
+    }
+}
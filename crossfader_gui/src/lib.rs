@@ -1,21 +1,42 @@
+use atomic_float::AtomicF32;
 use nih_plug::{
     params::{FloatParam, Params},
     plugin::Plugin,
     prelude::*,
 };
 use nih_plug_vizia::ViziaState;
-use std::sync::Arc;
+use std::sync::{atomic::Ordering, Arc};
 mod editor;
-use audio_util::{self, buffer::get_sample_at_position, crossfade};
+use audio_util::{
+    self, buffer,
+    crossfade::CrossfadeParams,
+    envelope::AutoFadeParams,
+    fader::FaderChain,
+    gesture::{GestureLooper, GestureParams},
+    midi::MidiParams,
+    smoothing::SmoothingParams,
+    transition::{Grid, TransitionParams},
+};
 
 struct XFader {
     params: Arc<XFaderParams>,
+    chain: FaderChain,
+    /// how much of the constant gain law the adaptive mode is using right now,
+    /// negative when the adaptive mode is off
+    law: Arc<AtomicF32>,
+    /// where the fader ended up at the end of the last buffer, after transitions and auto fading
+    position: Arc<AtomicF32>,
+    gesture: GestureLooper,
 }
 
 impl Default for XFader {
     fn default() -> Self {
         Self {
             params: Arc::new(XFaderParams::default()),
+            chain: FaderChain::default(),
+            law: Arc::new(AtomicF32::new(-1.0)),
+            position: Arc::new(AtomicF32::new(0.5)),
+            gesture: GestureLooper::default(),
         }
     }
 }
@@ -27,6 +48,23 @@ pub struct XFaderParams {
 
     #[id = "53c56370-01c5-4820-a977-1dec2eef1af3"]
     pub fade_strength: FloatParam,
+
+    #[id = "0519ed67-39d8-484b-aa86-64f61cb3c54a"]
+    pub adaptive: BoolParam,
+
+    #[nested(group = "Curve")]
+    pub curve: CrossfadeParams,
+
+    #[nested(group = "Transition")]
+    pub transition: TransitionParams,
+    #[nested(group = "Auto Fade")]
+    pub auto_fade: AutoFadeParams,
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+    #[nested(group = "Smoothing")]
+    pub smoothing: SmoothingParams,
+    #[nested(group = "Gesture")]
+    pub gesture: GestureParams,
 }
 
 impl Default for XFaderParams {
@@ -38,9 +76,15 @@ impl Default for XFaderParams {
                 0.5,
                 FloatRange::Linear { min: 0.0, max: 1.0 },
             )
-            .with_smoother(SmoothingStyle::Linear(0.5))
             .with_unit(" %")
             .with_value_to_string(formatters::v2s_f32_percentage(2)),
+            adaptive: BoolParam::new("Correlation Adaptive", false),
+            curve: CrossfadeParams::default(),
+            transition: TransitionParams::default(),
+            auto_fade: AutoFadeParams::default(),
+            midi: MidiParams::new(&["X"]),
+            smoothing: SmoothingParams::default(),
+            gesture: GestureParams::default(),
         }
     }
 }
@@ -51,6 +95,7 @@ impl Plugin for XFader {
     const URL: &'static str = "https://github.com/fruitsbat/audioplugins";
     const EMAIL: &'static str = "zoe@kittycat.homes";
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
     const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
         // stereo
@@ -79,29 +124,90 @@ impl Plugin for XFader {
     }
 
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
-        editor::create(self.params.clone(), self.params.editor_state.clone())
+        editor::create(
+            self.params.clone(),
+            self.law.clone(),
+            self.position.clone(),
+            self.params.editor_state.clone(),
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.chain.initialize(
+            buffer_config.sample_rate,
+            buffer_config.max_buffer_size as usize,
+        );
+        self.gesture.load(&self.params.gesture.gesture);
+        self.chain.midi.load(&self.params.midi.notes);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.chain.reset(self.params.fade_strength.value());
     }
 
     fn process(
         &mut self,
         buffer: &mut nih_plug::prelude::Buffer,
         aux: &mut nih_plug::prelude::AuxiliaryBuffers,
-        _context: &mut impl nih_plug::prelude::ProcessContext<Self>,
+        context: &mut impl nih_plug::prelude::ProcessContext<Self>,
     ) -> nih_plug::prelude::ProcessStatus {
         {
-            let linear_crossfade_power = self.params.fade_strength.smoothed.next();
-            let constant_power_crossfade = crossfade::constant_power(linear_crossfade_power);
-            let x0_fade_strength = constant_power_crossfade;
-            let x1_fade_strength = 1.0 - constant_power_crossfade;
-
-            // apply to main audio input
-            for (sample_index, channel_samples) in buffer.iter_samples().enumerate() {
-                for (channel_index, sample) in channel_samples.into_iter().enumerate() {
-                    *sample *= x0_fade_strength;
-                    *sample +=
-                        get_sample_at_position(channel_index, sample_index, aux) * x1_fade_strength;
-                }
-            }
+            let fader = self.params.fade_strength.value();
+            self.chain.begin_block(
+                fader,
+                self.params.curve.crossfade(),
+                &self.params.transition,
+                &self.params.smoothing,
+                context.transport(),
+            );
+            self.chain.measure(
+                self.params.adaptive.value(),
+                &self.params.auto_fade,
+                buffer.as_slice_immutable(),
+                buffer::sidechain(aux),
+                context.transport().sample_rate,
+            );
+            let law = match self.chain.law() {
+                Some(law) => law.clamp(0.0, 1.0),
+                None => -1.0,
+            };
+
+            self.gesture.update(
+                &self.params.gesture.settings(),
+                self.params.gesture.clear.value(),
+                &Grid::from_transport(context.transport()),
+                context.transport().sample_rate,
+            );
+            let midi_settings = self.params.midi.settings(context.transport().sample_rate);
+            let mut next_event = context.next_event();
+            let mut sample_index = 0;
+
+            let mut weights = [0.0; 2];
+            let mut position = self.position.load(Ordering::Relaxed);
+            buffer::mix_in_place(buffer, aux, &mut weights, |weights| {
+                self.chain.midi.handle_events(
+                    sample_index,
+                    &mut next_event,
+                    || context.next_event(),
+                    &midi_settings,
+                );
+                sample_index += 1;
+
+                let mut fade_position = [fader];
+                self.chain.midi.next(&mut fade_position);
+                self.gesture.next(&mut fade_position);
+                position = self.chain.next_weights(fade_position[0], weights);
+            });
+            self.law.store(law, Ordering::Relaxed);
+            self.position.store(position, Ordering::Relaxed);
+            self.gesture.publish(&self.params.gesture.gesture);
+            self.chain.midi.publish(&self.params.midi.notes);
 
             ProcessStatus::Normal
         }
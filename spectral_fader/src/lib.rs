@@ -0,0 +1,195 @@
+use audio_util::{
+    buffer,
+    midi::{MidiControl, MidiParams},
+    spectral::{FftSize, SpectralMorph, SpectralMorphMode},
+};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+struct SpectralFade {
+    params: Arc<SpectralFadeParams>,
+    /// one morph per channel for every fft size, so switching sizes doesn't allocate
+    morphs: Vec<Vec<SpectralMorph>>,
+    /// the fft size the latency was last reported for
+    fft_size: FftSize,
+    midi: MidiControl,
+}
+
+impl Default for SpectralFade {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(SpectralFadeParams::default()),
+            morphs: Vec::new(),
+            fft_size: FftSize::Size2048,
+            midi: MidiControl::default(),
+        }
+    }
+}
+
+#[derive(Params)]
+struct SpectralFadeParams {
+    #[id = "X"]
+    pub position: FloatParam,
+    #[id = "mode"]
+    pub mode: EnumParam<SpectralMorphMode>,
+    #[id = "fft-size"]
+    pub fft_size: EnumParam<FftSize>,
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+}
+
+impl Default for SpectralFadeParams {
+    fn default() -> Self {
+        Self {
+            position: FloatParam::new("X Morph", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(10.0))
+                .with_step_size(0.0001),
+            mode: EnumParam::new("Mode", SpectralMorphMode::Magnitude),
+            fft_size: EnumParam::new("FFT Size", FftSize::Size2048),
+            midi: MidiParams::new(&["X"]),
+        }
+    }
+}
+
+impl Plugin for SpectralFade {
+    // metadata
+    const NAME: &'static str = "Spectral Fader";
+    const VENDOR: &'static str = "zoe bat";
+    const URL: &'static str = "https://zoe.kittycat.homes";
+    const EMAIL: &'static str = "zoe@kittycat.homes";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        // stereo
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+
+            names: PortNames {
+                layout: Some("Stereo"),
+                main_input: Some("Main"),
+                main_output: Some("Out"),
+                aux_inputs: &["Sidechain"],
+                aux_outputs: &[],
+            },
+        },
+    ];
+
+    type SysExMessage = ();
+
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        audio_io_layout: &AudioIOLayout,
+        _buffer_config: &BufferConfig,
+        context: &mut impl InitContext<Self>,
+    ) -> bool {
+        let channels = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or_default() as usize;
+        self.morphs = FftSize::ALL
+            .iter()
+            .map(|size| (0..channels).map(|_| SpectralMorph::new(*size)).collect())
+            .collect();
+
+        self.fft_size = self.params.fft_size.value();
+        context.set_latency_samples(self.fft_size.samples() as u32);
+        self.midi.load(&self.params.midi.notes);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.morphs
+            .iter_mut()
+            .flatten()
+            .for_each(SpectralMorph::reset);
+        self.midi.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let fft_size = self.params.fft_size.value();
+        if fft_size != self.fft_size {
+            self.fft_size = fft_size;
+            // the new morph starts from silence instead of whatever it held last time
+            self.morphs[fft_size as usize]
+                .iter_mut()
+                .for_each(SpectralMorph::reset);
+            context.set_latency_samples(fft_size.samples() as u32);
+        }
+
+        let mode = self.params.mode.value();
+        let morphs = &mut self.morphs[fft_size as usize];
+
+        self.midi.follow_faders(&[self.params.position.value()]);
+        let midi_settings = self.params.midi.settings(context.transport().sample_rate);
+        let mut next_event = context.next_event();
+
+        for (sample_index, channel_samples) in buffer.iter_samples().enumerate() {
+            self.midi.handle_events(
+                sample_index as u32,
+                &mut next_event,
+                || context.next_event(),
+                &midi_settings,
+            );
+            let mut position = [self.params.position.smoothed.next()];
+            self.midi.next(&mut position);
+            let [position] = position;
+            for ((channel_index, sample), morph) in channel_samples
+                .into_iter()
+                .enumerate()
+                .zip(morphs.iter_mut())
+            {
+                let side = buffer::get_sample_at_position(channel_index, sample_index, aux);
+                *sample = morph.process(*sample, side, position, mode);
+            }
+        }
+
+        self.midi.publish(&self.params.midi.notes);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for SpectralFade {
+    const CLAP_ID: &'static str = "fruitsuite.spectral_fader";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("morphs between main and sidechain in the frequency domain");
+    const CLAP_MANUAL_URL: Option<&'static str> = None;
+    const CLAP_SUPPORT_URL: Option<&'static str> = Some("https://zoe.kittycat.homes");
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::Stereo,
+        ClapFeature::Utility,
+        ClapFeature::Custom("Crossfade"),
+        ClapFeature::AudioEffect,
+    ];
+}
+
+impl Vst3Plugin for SpectralFade {
+    const VST3_CLASS_ID: [u8; 16] = *b"fruit.SpecFader0";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
+        Vst3SubCategory::Fx,
+        Vst3SubCategory::Stereo,
+        Vst3SubCategory::Tools,
+        Vst3SubCategory::Spectral,
+    ];
+}
+
+nih_export_clap!(SpectralFade);
+nih_export_vst3!(SpectralFade);
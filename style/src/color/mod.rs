@@ -0,0 +1,83 @@
+use nih_plug_vizia::vizia::{prelude::Color, vg};
+
+/// a colour that can go into a stylesheet or straight onto a canvas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// as a hex colour for css
+    pub fn css(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Self {
+        Color::rgb(rgb.r, rgb.g, rgb.b)
+    }
+}
+
+impl From<Rgb> for vg::Color {
+    fn from(rgb: Rgb) -> Self {
+        vg::Color::rgb(rgb.r, rgb.g, rgb.b)
+    }
+}
+
+/**
+every colour an editor uses. widgets draw lines in `text`, the quieter parts in `muted`,
+grids and frames in `border` and fill with `surface`
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// behind everything
+    pub background: Rgb,
+    /// behind sliders, buttons and the widgets
+    pub surface: Rgb,
+    pub border: Rgb,
+    pub text: Rgb,
+    /// for less important lines and text
+    pub muted: Rgb,
+    /// slider fills, pressed buttons and whatever is active
+    pub accent: Rgb,
+    /// text on top of `accent`
+    pub on_accent: Rgb,
+}
+
+pub const DARK: Palette = Palette {
+    background: Rgb::new(24, 24, 27),
+    surface: Rgb::new(40, 40, 44),
+    border: Rgb::new(80, 80, 86),
+    text: Rgb::new(200, 200, 204),
+    muted: Rgb::new(120, 120, 126),
+    accent: Rgb::new(232, 128, 72),
+    on_accent: Rgb::new(24, 24, 27),
+};
+
+pub const LIGHT: Palette = Palette {
+    background: Rgb::new(244, 243, 240),
+    surface: Rgb::new(255, 255, 255),
+    border: Rgb::new(196, 194, 190),
+    text: Rgb::new(36, 36, 40),
+    muted: Rgb::new(128, 126, 122),
+    accent: Rgb::new(204, 96, 40),
+    on_accent: Rgb::new(255, 255, 255),
+};
+
+/// only black, white and yellow, for when the others are hard to read
+pub const HIGH_CONTRAST: Palette = Palette {
+    background: Rgb::new(0, 0, 0),
+    surface: Rgb::new(0, 0, 0),
+    border: Rgb::new(255, 255, 255),
+    text: Rgb::new(255, 255, 255),
+    muted: Rgb::new(255, 255, 255),
+    accent: Rgb::new(255, 221, 0),
+    on_accent: Rgb::new(0, 0, 0),
+};
@@ -1 +1,71 @@
-pub mod size;
\ No newline at end of file
+use nih_plug_vizia::vizia::prelude::*;
+
+pub mod size;
+
+/// the family name both fira files register under
+pub const FIRA_SANS: &str = "Fira Sans";
+pub const FIRA_SANS_REGULAR: &[u8] = include_bytes!("../../assets/FiraSans-Regular.ttf");
+pub const FIRA_SANS_BOLD: &[u8] = include_bytes!("../../assets/FiraSans-Bold.ttf");
+
+/// makes both weights of fira sans available to the editor, before anything uses them
+pub fn register(cx: &mut Context) {
+    cx.add_font_mem(FIRA_SANS_REGULAR);
+    cx.add_font_mem(FIRA_SANS_BOLD);
+}
+
+/// the weights there is a fira file for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weight {
+    Regular,
+    Bold,
+}
+
+impl Weight {
+    pub fn css(self) -> &'static str {
+        match self {
+            Weight::Regular => "normal",
+            Weight::Bold => "bold",
+        }
+    }
+}
+
+/// one kind of text, always in fira sans
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub size: f32,
+    pub weight: Weight,
+}
+
+impl TextStyle {
+    /// the declarations for a css rule
+    pub fn css(&self) -> String {
+        format!(
+            "font-family: \"{FIRA_SANS}\"; font-size: {}px; font-weight: {};",
+            self.size,
+            self.weight.css()
+        )
+    }
+}
+
+/// the name of the plugin at the top of the editor
+pub const TITLE: TextStyle = TextStyle {
+    size: size::XL,
+    weight: Weight::Bold,
+};
+
+/// the heading over a group of controls
+pub const SECTION: TextStyle = TextStyle {
+    size: size::XS,
+    weight: Weight::Bold,
+};
+
+pub const LABEL: TextStyle = TextStyle {
+    size: size::SM,
+    weight: Weight::Regular,
+};
+
+/// numbers the plugin reports back, like the value on a slider
+pub const VALUE: TextStyle = TextStyle {
+    size: size::XS,
+    weight: Weight::Regular,
+};
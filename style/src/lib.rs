@@ -1,3 +1,6 @@
 pub mod color;
 pub mod font;
-pub mod spacing;
\ No newline at end of file
+pub mod radius;
+pub mod spacing;
+pub mod theme;
+pub mod widgets;
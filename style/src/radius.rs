@@ -0,0 +1,3 @@
+pub const SM: f32 = 2.0;
+pub const MD: f32 = 4.0;
+pub const LG: f32 = 8.0;
@@ -1 +1,6 @@
-pub const MD: u16 = 4;
\ No newline at end of file
+pub const XS: f32 = 1.0;
+pub const SM: f32 = 2.0;
+pub const MD: f32 = 4.0;
+pub const LG: f32 = 8.0;
+pub const XL: f32 = 16.0;
+pub const XXL: f32 = 32.0;
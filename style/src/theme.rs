@@ -0,0 +1,106 @@
+use nih_plug_vizia::vizia::prelude::*;
+
+use crate::color::Palette;
+use crate::{font, radius, spacing};
+
+/// how tall sliders and buttons are
+const CONTROL_HEIGHT: f32 = 24.0;
+const CONTROL_WIDTH: f32 = 180.0;
+
+/**
+registers the fonts and adds the stylesheet for `palette` on top of the nih_plug one,
+so it goes after `create_vizia_editor` with `ViziaTheming::Custom`
+ */
+pub fn install(cx: &mut Context, palette: &Palette) {
+    font::register(cx);
+    cx.add_stylesheet(CSS::from_string(&stylesheet(palette)))
+        .expect("the generated stylesheet should always parse");
+}
+
+/**
+the whole stylesheet for `palette`. the editor root gets the background, labels with the
+`title` or `section` class become headings, ones with the `value` class read out numbers
+and the widgets in `widgets` draw with `color`, `outline-color`, `border-color` and
+`background-color`
+ */
+pub fn stylesheet(palette: &Palette) -> String {
+    let background = palette.background.css();
+    let surface = palette.surface.css();
+    let border = palette.border.css();
+    let text = palette.text.css();
+    let muted = palette.muted.css();
+    let accent = palette.accent.css();
+    let on_accent = palette.on_accent.css();
+
+    format!(
+        r#"
+:root {{
+    background-color: {background};
+    color: {text};
+    {label}
+}}
+
+label {{
+    color: {text};
+    {label}
+}}
+
+label.title {{
+    {title}
+    bottom: {lg}px;
+}}
+
+label.section {{
+    color: {muted};
+    {section}
+    top: {lg}px;
+}}
+
+label.value {{
+    {value}
+}}
+
+param-slider, param-button {{
+    height: {CONTROL_HEIGHT}px;
+    background-color: {surface};
+    border-color: {border};
+    border-width: 1px;
+    border-radius: {radius_sm}px;
+}}
+
+param-slider {{
+    width: {CONTROL_WIDTH}px;
+}}
+
+param-slider .fill {{
+    background-color: {accent};
+}}
+
+param-slider .value {{
+    color: {text};
+    {value}
+}}
+
+param-button:checked {{
+    background-color: {accent};
+    color: {on_accent};
+}}
+
+xy-pad, xyz-cube, crossfader, curve-plot, path-view {{
+    color: {text};
+    outline-color: {muted};
+    border-color: {border};
+    background-color: {surface};
+    top: {md}px;
+    bottom: {md}px;
+}}
+"#,
+        md = spacing::MD,
+        lg = spacing::LG,
+        radius_sm = radius::SM,
+        title = font::TITLE.css(),
+        section = font::SECTION.css(),
+        label = font::LABEL.css(),
+        value = font::VALUE.css(),
+    )
+}
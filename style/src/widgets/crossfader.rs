@@ -0,0 +1,267 @@
+use atomic_float::AtomicF32;
+use nih_plug::prelude::Param;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use nih_plug_vizia::widgets::param_base::ParamWidgetBase;
+use std::sync::{atomic::Ordering, Arc};
+
+use super::WidgetColors;
+use crate::font;
+
+/// room at either end of the track for the labels
+const TRACK_INSET: f32 = 24.0;
+const CAP_WIDTH: f32 = 14.0;
+/// how close to the middle the fader has to get to snap to it
+const DETENT: f32 = 0.02;
+/// how many lines each curve in the plot is made of
+const PLOT_LINES: usize = 64;
+
+/**
+a horizontal dj crossfader for one param, a on the left and b on the right.
+it snaps to the middle when dragged close to it and a double click resets it
+ */
+pub struct Crossfader {
+    param: ParamWidgetBase,
+    /// where the plugin really has the fader, after transitions and the like
+    live: Option<Arc<AtomicF32>>,
+    /// how far from the middle of the cap it got grabbed, `None` while not dragging
+    grab: Option<f32>,
+}
+
+impl Crossfader {
+    pub fn new<L, Params, P, FMap>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+        labels: [&'static str; 2],
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: 'static + Fn(&Params) -> &P + Copy,
+    {
+        Self {
+            param: ParamWidgetBase::new(cx, params, params_to_param),
+            live: None,
+            grab: None,
+        }
+        .build(cx, |cx| {
+            for (side, label) in labels.into_iter().enumerate() {
+                let (left, right) = match side {
+                    0 => (Pixels(0.0), Stretch(1.0)),
+                    _ => (Stretch(1.0), Pixels(0.0)),
+                };
+                Label::new(cx, label)
+                    .position_type(PositionType::SelfDirected)
+                    .left(left)
+                    .right(right)
+                    .top(Stretch(1.0))
+                    .bottom(Stretch(1.0))
+                    .font_size(font::size::SM)
+                    .hoverable(false);
+            }
+        })
+    }
+
+    /// the track runs from the first value to the second, in window coordinates
+    fn track(bounds: BoundingBox) -> (f32, f32) {
+        (
+            bounds.x + TRACK_INSET,
+            (bounds.w - 2.0 * TRACK_INSET).max(1.0),
+        )
+    }
+
+    fn set_from_mouse(&self, cx: &mut EventContext, x: f32) {
+        let (start, width) = Self::track(cx.bounds());
+        let value = ((x - self.grab.unwrap_or_default() - start) / width).clamp(0.0, 1.0);
+        let value = match (value - 0.5).abs() < DETENT {
+            true => 0.5,
+            false => value,
+        };
+        self.param.set_normalized_value(cx, value);
+    }
+}
+
+pub trait CrossfaderExt {
+    /// also marks where the plugin really has the fader
+    fn live_position(self, position: Arc<AtomicF32>) -> Self;
+}
+
+impl CrossfaderExt for Handle<'_, Crossfader> {
+    fn live_position(self, position: Arc<AtomicF32>) -> Self {
+        self.modify(|fader| fader.live = Some(position))
+    }
+}
+
+impl View for Crossfader {
+    fn element(&self) -> Option<&'static str> {
+        Some("crossfader")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                let x = cx.mouse().cursorx;
+                let (start, width) = Self::track(cx.bounds());
+                let cap = start + self.param.unmodulated_normalized_value() * width;
+                // grabbing the cap keeps it under the mouse, clicking the track jumps there
+                self.grab = match (x - cap).abs() <= CAP_WIDTH * 0.5 {
+                    true => Some(x - cap),
+                    false => Some(0.0),
+                };
+                cx.capture();
+                cx.set_active(true);
+                self.param.begin_set_parameter(cx);
+                self.set_from_mouse(cx, x);
+                meta.consume();
+            }
+            WindowEvent::MouseDoubleClick(MouseButton::Left) => {
+                self.param.begin_set_parameter(cx);
+                self.param
+                    .set_normalized_value(cx, self.param.default_normalized_value());
+                self.param.end_set_parameter(cx);
+                meta.consume();
+            }
+            WindowEvent::MouseMove(x, _) if self.grab.is_some() => {
+                self.set_from_mouse(cx, *x);
+            }
+            WindowEvent::MouseUp(MouseButton::Left) if self.grab.is_some() => {
+                self.grab = None;
+                cx.release();
+                cx.set_active(false);
+                self.param.end_set_parameter(cx);
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let colors = WidgetColors::from_style(cx);
+        let (start, width) = Self::track(bounds);
+        let middle = bounds.y + bounds.h * 0.5;
+        let line = vg::Paint::color(colors.line).with_line_width(1.0);
+
+        let mut track = vg::Path::new();
+        track.move_to(start, middle);
+        track.line_to(start + width, middle);
+        // the detent gets a longer tick than the ends
+        for (x, height) in [(0.0, 0.25), (0.5, 0.4), (1.0, 0.25)] {
+            track.move_to(start + x * width, middle - bounds.h * height);
+            track.line_to(start + x * width, middle + bounds.h * height);
+        }
+        canvas.stroke_path(&mut track, &line);
+
+        if let Some(live) = &self.live {
+            let x = start + live.load(Ordering::Relaxed).clamp(0.0, 1.0) * width;
+            let mut marker = vg::Path::new();
+            marker.move_to(x, bounds.y + bounds.h);
+            marker.line_to(x - 4.0, bounds.y + bounds.h - 6.0);
+            marker.line_to(x + 4.0, bounds.y + bounds.h - 6.0);
+            marker.close();
+            canvas.fill_path(&mut marker, &vg::Paint::color(colors.line));
+        }
+
+        let x = start + self.param.unmodulated_normalized_value() * width;
+        let mut cap = vg::Path::new();
+        cap.rounded_rect(
+            x - CAP_WIDTH * 0.5,
+            bounds.y + 4.0,
+            CAP_WIDTH,
+            bounds.h - 12.0,
+            2.0,
+        );
+        canvas.fill_path(&mut cap, &vg::Paint::color(colors.fill));
+        canvas.stroke_path(&mut cap, &line);
+        let mut groove = vg::Path::new();
+        groove.move_to(x, bounds.y + 8.0);
+        groove.line_to(x, bounds.y + bounds.h - 12.0);
+        canvas.stroke_path(&mut groove, &line);
+    }
+}
+
+/**
+plots the gain of both sides across the whole fader, a dimmer than b,
+with a line and dots where the fader is right now. `gains` gets asked again
+on every redraw, so it can read the curve params
+ */
+pub struct CurvePlot {
+    gains: Box<dyn Fn(f32) -> (f32, f32)>,
+    position: Arc<AtomicF32>,
+}
+
+impl CurvePlot {
+    pub fn new(
+        cx: &mut Context,
+        gains: impl Fn(f32) -> (f32, f32) + 'static,
+        position: Arc<AtomicF32>,
+    ) -> Handle<Self> {
+        Self {
+            gains: Box::new(gains),
+            position,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl View for CurvePlot {
+    fn element(&self) -> Option<&'static str> {
+        Some("curve-plot")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let colors = WidgetColors::from_style(cx);
+        let to_screen = |position: f32, gain: f32| {
+            (
+                bounds.x + position * bounds.w,
+                bounds.y + (1.0 - gain.clamp(0.0, 1.0)) * bounds.h,
+            )
+        };
+
+        let mut frame = vg::Path::new();
+        frame.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.stroke_path(
+            &mut frame,
+            &vg::Paint::color(colors.grid).with_line_width(1.0),
+        );
+
+        let mut curves = [vg::Path::new(), vg::Path::new()];
+        for index in 0..=PLOT_LINES {
+            let position = index as f32 / PLOT_LINES as f32;
+            let (a, b) = (self.gains)(position);
+            for (curve, gain) in curves.iter_mut().zip([a, b]) {
+                let (x, y) = to_screen(position, gain);
+                match index {
+                    0 => curve.move_to(x, y),
+                    _ => curve.line_to(x, y),
+                }
+            }
+        }
+        for (curve, color) in curves.iter_mut().zip([colors.muted, colors.line]) {
+            canvas.stroke_path(curve, &vg::Paint::color(color).with_line_width(2.0));
+        }
+
+        let position = self.position.load(Ordering::Relaxed).clamp(0.0, 1.0);
+        let (x, _) = to_screen(position, 0.0);
+        let mut marker = vg::Path::new();
+        marker.move_to(x, bounds.y);
+        marker.line_to(x, bounds.y + bounds.h);
+        let (a, b) = (self.gains)(position);
+        for gain in [a, b] {
+            let (x, y) = to_screen(position, gain);
+            marker.circle(x, y, 3.0);
+        }
+        canvas.stroke_path(
+            &mut marker,
+            &vg::Paint::color(colors.line).with_line_width(1.0),
+        );
+    }
+}
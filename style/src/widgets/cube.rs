@@ -0,0 +1,294 @@
+use atomic_float::AtomicF32;
+use nih_plug::prelude::Param;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use nih_plug_vizia::widgets::param_base::ParamWidgetBase;
+use std::sync::{atomic::Ordering, Arc};
+
+use super::WidgetColors;
+
+/// every corner of the cube, one per source
+pub const CORNERS: usize = 8;
+
+/// how far away the camera is, in cube widths from the center. closer is more perspective
+const CAMERA_DISTANCE: f32 = 3.0;
+/// how much of the view the cube takes up
+const CUBE_SCALE: f32 = 0.55;
+/// radians per pixel when orbiting
+const ORBIT_SPEED: f32 = 0.01;
+/// how much of the z axis one scroll step moves
+const SCROLL_STEP: f32 = 0.05;
+const HANDLE_RADIUS: f32 = 6.0;
+const CURSOR_RADIUS: f32 = 3.0;
+const CORNER_RADIUS: f32 = 2.0;
+/// corners at full weight get this much bigger
+const WEIGHT_RADIUS: f32 = 10.0;
+
+/**
+draws the cube of sources in perspective with the point moving through it, and each
+corner sized by how much of it is playing. everything is plain vector paths.
+
+dragging with the left button moves x and y on the plane the point is on, the scroll
+wheel moves z and dragging with the right button orbits the camera around the cube
+ */
+pub struct XyzCube {
+    x: ParamWidgetBase,
+    y: ParamWidgetBase,
+    z: ParamWidgetBase,
+    /// where the plugin really has the axes
+    live: Option<Arc<[AtomicF32; 3]>>,
+    /// how loud every corner is right now
+    weights: Option<Arc<[AtomicF32; CORNERS]>>,
+    /// around the up axis, then tilted towards the camera
+    yaw: f32,
+    pitch: f32,
+    drag: Option<Drag>,
+    /// the mouse position last event while dragging
+    last_mouse: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Drag {
+    Move,
+    Orbit,
+}
+
+impl XyzCube {
+    pub fn new<L, Params, PX, PY, PZ, FX, FY, FZ>(
+        cx: &mut Context,
+        params: L,
+        x: FX,
+        y: FY,
+        z: FZ,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        PX: Param + 'static,
+        PY: Param + 'static,
+        PZ: Param + 'static,
+        FX: 'static + Fn(&Params) -> &PX + Copy,
+        FY: 'static + Fn(&Params) -> &PY + Copy,
+        FZ: 'static + Fn(&Params) -> &PZ + Copy,
+    {
+        Self {
+            x: ParamWidgetBase::new(cx, params.clone(), x),
+            y: ParamWidgetBase::new(cx, params.clone(), y),
+            z: ParamWidgetBase::new(cx, params, z),
+            live: None,
+            weights: None,
+            yaw: -0.6,
+            pitch: 0.4,
+            drag: None,
+            last_mouse: (0.0, 0.0),
+        }
+        .build(cx, |_| {})
+    }
+
+    fn params(&self) -> [f32; 3] {
+        [
+            self.x.unmodulated_normalized_value(),
+            self.y.unmodulated_normalized_value(),
+            self.z.unmodulated_normalized_value(),
+        ]
+    }
+
+    /// where a point in the cube ends up on screen
+    fn project(&self, bounds: BoundingBox, point: [f32; 3]) -> (f32, f32) {
+        let [x, y, z] = point.map(|value| value - 0.5);
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+
+        let (x, z) = (x * yaw_cos - z * yaw_sin, x * yaw_sin + z * yaw_cos);
+        let (y, z) = (y * pitch_cos - z * pitch_sin, y * pitch_sin + z * pitch_cos);
+        let scale = CAMERA_DISTANCE / (CAMERA_DISTANCE + z) * bounds.w.min(bounds.h) * CUBE_SCALE;
+        (
+            bounds.x + bounds.w * 0.5 + x * scale,
+            bounds.y + bounds.h * 0.5 - y * scale,
+        )
+    }
+
+    /**
+    moves x and y so the point follows the mouse on its plane. works out how far a small
+    step along each axis moves on screen right where the point is, and undoes that
+     */
+    fn drag_point(&self, cx: &mut EventContext, delta: (f32, f32)) {
+        let bounds = cx.bounds();
+        let point = self.params();
+        let (start_x, start_y) = self.project(bounds, point);
+        let axis_step = |axis: usize| {
+            let mut moved = point;
+            moved[axis] += 0.01;
+            let (x, y) = self.project(bounds, moved);
+            ((x - start_x) / 0.01, (y - start_y) / 0.01)
+        };
+        let (x_x, x_y) = axis_step(0);
+        let (y_x, y_y) = axis_step(1);
+
+        // seen edge on the plane doesn't go anywhere useful
+        let determinant = x_x * y_y - y_x * x_y;
+        if determinant.abs() < 1.0 {
+            return;
+        }
+        let x = (delta.0 * y_y - delta.1 * y_x) / determinant;
+        let y = (delta.1 * x_x - delta.0 * x_y) / determinant;
+        self.x
+            .set_normalized_value(cx, (point[0] + x).clamp(0.0, 1.0));
+        self.y
+            .set_normalized_value(cx, (point[1] + y).clamp(0.0, 1.0));
+    }
+}
+
+pub trait XyzCubeExt {
+    /// also draws a dot where the plugin really has the axes
+    fn live_cursor(self, position: Arc<[AtomicF32; 3]>) -> Self;
+    /// sizes every corner by its weight, in source order
+    fn weights(self, weights: Arc<[AtomicF32; CORNERS]>) -> Self;
+}
+
+impl XyzCubeExt for Handle<'_, XyzCube> {
+    fn live_cursor(self, position: Arc<[AtomicF32; 3]>) -> Self {
+        self.modify(|cube| cube.live = Some(position))
+    }
+
+    fn weights(self, weights: Arc<[AtomicF32; CORNERS]>) -> Self {
+        self.modify(|cube| cube.weights = Some(weights))
+    }
+}
+
+impl View for XyzCube {
+    fn element(&self) -> Option<&'static str> {
+        Some("xyz-cube")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(button @ (MouseButton::Left | MouseButton::Right)) => {
+                let drag = match button {
+                    MouseButton::Left => Drag::Move,
+                    _ => Drag::Orbit,
+                };
+                if drag == Drag::Move {
+                    self.x.begin_set_parameter(cx);
+                    self.y.begin_set_parameter(cx);
+                }
+                self.drag = Some(drag);
+                self.last_mouse = (cx.mouse().cursorx, cx.mouse().cursory);
+                cx.capture();
+                cx.set_active(true);
+                meta.consume();
+            }
+            WindowEvent::MouseMove(x, y) => {
+                let delta = (*x - self.last_mouse.0, *y - self.last_mouse.1);
+                self.last_mouse = (*x, *y);
+                match self.drag {
+                    Some(Drag::Move) => self.drag_point(cx, delta),
+                    Some(Drag::Orbit) => {
+                        self.yaw += delta.0 * ORBIT_SPEED;
+                        self.pitch = (self.pitch + delta.1 * ORBIT_SPEED).clamp(-1.5, 1.5);
+                        cx.needs_redraw();
+                    }
+                    None => {}
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left | MouseButton::Right) if self.drag.is_some() => {
+                if self.drag == Some(Drag::Move) {
+                    self.x.end_set_parameter(cx);
+                    self.y.end_set_parameter(cx);
+                }
+                self.drag = None;
+                cx.release();
+                cx.set_active(false);
+                meta.consume();
+            }
+            WindowEvent::MouseScroll(_, scroll) => {
+                let z =
+                    (self.z.unmodulated_normalized_value() + scroll * SCROLL_STEP).clamp(0.0, 1.0);
+                self.z.begin_set_parameter(cx);
+                self.z.set_normalized_value(cx, z);
+                self.z.end_set_parameter(cx);
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let colors = WidgetColors::from_style(cx);
+        let corner =
+            |index: usize| [index & 1, (index >> 1) & 1, (index >> 2) & 1].map(|bit| bit as f32);
+
+        // every edge joins two corners that only differ on one axis
+        let mut edges = vg::Path::new();
+        for from in 0..CORNERS {
+            for axis in [1, 2, 4] {
+                if from & axis == 0 {
+                    let (x, y) = self.project(bounds, corner(from));
+                    edges.move_to(x, y);
+                    let (x, y) = self.project(bounds, corner(from | axis));
+                    edges.line_to(x, y);
+                }
+            }
+        }
+        canvas.stroke_path(
+            &mut edges,
+            &vg::Paint::color(colors.line).with_line_width(1.0),
+        );
+
+        // the plane dragging moves on
+        let point = self.params();
+        let mut plane = vg::Path::new();
+        for (index, [x, y]) in [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+            .into_iter()
+            .enumerate()
+        {
+            let (x, y) = self.project(bounds, [x, y, point[2]]);
+            match index {
+                0 => plane.move_to(x, y),
+                _ => plane.line_to(x, y),
+            }
+        }
+        plane.close();
+        canvas.stroke_path(
+            &mut plane,
+            &vg::Paint::color(colors.grid).with_line_width(1.0),
+        );
+
+        for index in 0..CORNERS {
+            let weight = self
+                .weights
+                .as_ref()
+                .map_or(0.0, |weights| weights[index].load(Ordering::Relaxed))
+                .clamp(0.0, 1.0);
+            let (x, y) = self.project(bounds, corner(index));
+            let mut dot = vg::Path::new();
+            dot.circle(x, y, CORNER_RADIUS + weight * WEIGHT_RADIUS);
+            canvas.fill_path(&mut dot, &vg::Paint::color(colors.muted));
+        }
+
+        let (x, y) = self.project(bounds, point);
+        let mut handle = vg::Path::new();
+        handle.circle(x, y, HANDLE_RADIUS);
+        canvas.stroke_path(
+            &mut handle,
+            &vg::Paint::color(colors.line).with_line_width(2.0),
+        );
+
+        if let Some(live) = &self.live {
+            let (x, y) = self.project(
+                bounds,
+                [
+                    live[0].load(Ordering::Relaxed),
+                    live[1].load(Ordering::Relaxed),
+                    live[2].load(Ordering::Relaxed),
+                ],
+            );
+            let mut cursor = vg::Path::new();
+            cursor.circle(x, y, CURSOR_RADIUS);
+            canvas.fill_path(&mut cursor, &vg::Paint::color(colors.line));
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+mod crossfader;
+mod cube;
+mod xy_pad;
+
+pub use crossfader::{Crossfader, CrossfaderExt, CurvePlot};
+pub use cube::{XyzCube, XyzCubeExt, CORNERS};
+pub use xy_pad::{XyPad, XyPadExt};
+
+/**
+the colours a widget draws with, out of its style so they follow the palette.
+`color` is for the main lines, `outline-color` for the quieter ones,
+`border-color` for frames and grids and `background-color` for fills
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct WidgetColors {
+    pub line: vg::Color,
+    pub muted: vg::Color,
+    pub grid: vg::Color,
+    pub fill: vg::Color,
+}
+
+impl WidgetColors {
+    pub fn from_style(cx: &DrawContext) -> Self {
+        Self {
+            line: cx.font_color().into(),
+            muted: cx.outline_color().into(),
+            grid: cx.border_color().into(),
+            fill: cx.background_color().into(),
+        }
+    }
+}
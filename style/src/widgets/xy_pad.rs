@@ -0,0 +1,178 @@
+use atomic_float::AtomicF32;
+use nih_plug::prelude::Param;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use nih_plug_vizia::widgets::param_base::ParamWidgetBase;
+use std::sync::{atomic::Ordering, Arc};
+
+use super::WidgetColors;
+use crate::font;
+
+const HANDLE_RADIUS: f32 = 6.0;
+const CURSOR_RADIUS: f32 = 3.0;
+/// how far the corner labels sit from the edges
+const LABEL_INSET: f32 = 4.0;
+
+/**
+a square pad that moves two params at once, x goes right and y goes up.
+a drag is one automation gesture on both params and a double click resets them.
+the corners get labelled with the sources that play there
+ */
+pub struct XyPad {
+    x: ParamWidgetBase,
+    y: ParamWidgetBase,
+    /// where the plugin really has the axes, which is not always where the params are
+    live: Option<Arc<[AtomicF32; 2]>>,
+    dragging: bool,
+}
+
+impl XyPad {
+    /// `corners` go in source order, so bottom left, bottom right, top left and top right
+    pub fn new<L, Params, PX, PY, FX, FY>(
+        cx: &mut Context,
+        params: L,
+        x: FX,
+        y: FY,
+        corners: [&'static str; 4],
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        PX: Param + 'static,
+        PY: Param + 'static,
+        FX: 'static + Fn(&Params) -> &PX + Copy,
+        FY: 'static + Fn(&Params) -> &PY + Copy,
+    {
+        Self {
+            x: ParamWidgetBase::new(cx, params.clone(), x),
+            y: ParamWidgetBase::new(cx, params, y),
+            live: None,
+            dragging: false,
+        }
+        .build(cx, |cx| {
+            for (corner, label) in corners.into_iter().enumerate() {
+                let (left, right) = match corner & 1 == 0 {
+                    true => (Pixels(LABEL_INSET), Stretch(1.0)),
+                    false => (Stretch(1.0), Pixels(LABEL_INSET)),
+                };
+                let (top, bottom) = match corner & 2 == 0 {
+                    true => (Stretch(1.0), Pixels(LABEL_INSET)),
+                    false => (Pixels(LABEL_INSET), Stretch(1.0)),
+                };
+                Label::new(cx, label)
+                    .position_type(PositionType::SelfDirected)
+                    .left(left)
+                    .right(right)
+                    .top(top)
+                    .bottom(bottom)
+                    .font_size(font::size::XS)
+                    .hoverable(false);
+            }
+        })
+    }
+
+    fn set_from_mouse(&self, cx: &mut EventContext, x: f32, y: f32) {
+        let bounds = cx.bounds();
+        let x = ((x - bounds.x) / bounds.w).clamp(0.0, 1.0);
+        let y = (1.0 - (y - bounds.y) / bounds.h).clamp(0.0, 1.0);
+        self.x.set_normalized_value(cx, x);
+        self.y.set_normalized_value(cx, y);
+    }
+}
+
+pub trait XyPadExt {
+    /// also draws a dot where the plugin really has the axes
+    fn live_cursor(self, position: Arc<[AtomicF32; 2]>) -> Self;
+}
+
+impl XyPadExt for Handle<'_, XyPad> {
+    fn live_cursor(self, position: Arc<[AtomicF32; 2]>) -> Self {
+        self.modify(|pad| pad.live = Some(position))
+    }
+}
+
+impl View for XyPad {
+    fn element(&self) -> Option<&'static str> {
+        Some("xy-pad")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                self.dragging = true;
+                cx.capture();
+                cx.set_active(true);
+                self.x.begin_set_parameter(cx);
+                self.y.begin_set_parameter(cx);
+                let (x, y) = (cx.mouse().cursorx, cx.mouse().cursory);
+                self.set_from_mouse(cx, x, y);
+                meta.consume();
+            }
+            WindowEvent::MouseDoubleClick(MouseButton::Left) => {
+                for param in [&self.x, &self.y] {
+                    param.begin_set_parameter(cx);
+                    param.set_normalized_value(cx, param.default_normalized_value());
+                    param.end_set_parameter(cx);
+                }
+                meta.consume();
+            }
+            WindowEvent::MouseMove(x, y) if self.dragging => {
+                self.set_from_mouse(cx, *x, *y);
+            }
+            WindowEvent::MouseUp(MouseButton::Left) if self.dragging => {
+                self.dragging = false;
+                cx.release();
+                cx.set_active(false);
+                self.x.end_set_parameter(cx);
+                self.y.end_set_parameter(cx);
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let colors = WidgetColors::from_style(cx);
+        let to_screen = |x: f32, y: f32| (bounds.x + x * bounds.w, bounds.y + (1.0 - y) * bounds.h);
+        let line = vg::Paint::color(colors.line).with_line_width(1.0);
+
+        let mut frame = vg::Path::new();
+        frame.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.stroke_path(&mut frame, &line);
+
+        let mut cross = vg::Path::new();
+        let (center_x, center_y) = to_screen(0.5, 0.5);
+        cross.move_to(center_x, bounds.y);
+        cross.line_to(center_x, bounds.y + bounds.h);
+        cross.move_to(bounds.x, center_y);
+        cross.line_to(bounds.x + bounds.w, center_y);
+        canvas.stroke_path(
+            &mut cross,
+            &vg::Paint::color(colors.grid).with_line_width(1.0),
+        );
+
+        let (x, y) = to_screen(
+            self.x.unmodulated_normalized_value(),
+            self.y.unmodulated_normalized_value(),
+        );
+        let mut handle = vg::Path::new();
+        handle.circle(x, y, HANDLE_RADIUS);
+        canvas.stroke_path(
+            &mut handle,
+            &vg::Paint::color(colors.line).with_line_width(2.0),
+        );
+
+        if let Some(live) = &self.live {
+            let (x, y) = to_screen(
+                live[0].load(Ordering::Relaxed),
+                live[1].load(Ordering::Relaxed),
+            );
+            let mut cursor = vg::Path::new();
+            cursor.circle(x, y, CURSOR_RADIUS);
+            canvas.fill_path(&mut cursor, &vg::Paint::color(colors.line));
+        }
+    }
+}
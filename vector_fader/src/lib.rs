@@ -0,0 +1,307 @@
+use audio_util::{
+    buffer,
+    midi::{MidiControl, MidiParams},
+    vector::{self, VectorSource, VectorWeighting},
+};
+use nih_plug::prelude::*;
+use std::{f32::consts::TAU, sync::Arc};
+
+/// number of sources, the main input plus one per aux input
+const SOURCES: usize = 8;
+/// how many samples pass between two weight calculations, the weights are ramped in between
+/// since natural neighbour weights are too expensive to work out for every sample
+const CONTROL_INTERVAL: usize = 128;
+
+/// everything the weights depend on, they only get worked out again when this changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WeightInputs {
+    cursor: [f32; 2],
+    sources: [VectorSource; SOURCES],
+    weighting: VectorWeighting,
+    constant_power: bool,
+}
+
+struct VectorFade {
+    params: Arc<VectorFadeParams>,
+    /// the weights used for the current sample
+    weights: [f32; SOURCES],
+    /// where the weights are ramping to
+    target: [f32; SOURCES],
+    /// how much every weight moves per sample until the next calculation
+    weight_steps: [f32; SOURCES],
+    samples_until_update: usize,
+    /// what `target` was worked out from, `None` jumps straight to the next weights
+    /// instead of ramping
+    last_inputs: Option<WeightInputs>,
+    midi: MidiControl,
+}
+
+impl Default for VectorFade {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(VectorFadeParams::default()),
+            weights: [0.0; SOURCES],
+            target: [0.0; SOURCES],
+            weight_steps: [0.0; SOURCES],
+            samples_until_update: 0,
+            last_inputs: None,
+            midi: MidiControl::default(),
+        }
+    }
+}
+
+#[derive(Params)]
+struct VectorFadeParams {
+    #[id = "X"]
+    pub x_slider: FloatParam,
+    #[id = "Y"]
+    pub y_slider: FloatParam,
+    #[id = "weighting"]
+    pub weighting: EnumParam<VectorWeighting>,
+    #[id = "constant-power"]
+    pub constant_power: BoolParam,
+    #[nested(array, group = "Source")]
+    pub sources: [SourceParams; SOURCES],
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+}
+
+impl Default for VectorFadeParams {
+    fn default() -> Self {
+        Self {
+            x_slider: FloatParam::new("X", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(10.0)),
+            y_slider: FloatParam::new("Y", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(10.0)),
+            weighting: EnumParam::new("Weighting", VectorWeighting::InverseDistance),
+            constant_power: BoolParam::new("Constant Power", true),
+            sources: std::array::from_fn(SourceParams::new),
+            midi: MidiParams::new(&["X", "Y"]),
+        }
+    }
+}
+
+/// where a source sits on the plane, stored as params so it is saved with the plugin
+#[derive(Params)]
+struct SourceParams {
+    #[id = "x"]
+    pub x: FloatParam,
+    #[id = "y"]
+    pub y: FloatParam,
+    #[id = "radius"]
+    pub radius: FloatParam,
+}
+
+impl SourceParams {
+    /// spreads the sources evenly on a circle around the center
+    fn new(index: usize) -> Self {
+        let angle = index as f32 / SOURCES as f32 * TAU;
+        let number = index + 1;
+
+        Self {
+            x: FloatParam::new(
+                format!("Source {number} X"),
+                0.5 + angle.sin() * 0.4,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            y: FloatParam::new(
+                format!("Source {number} Y"),
+                0.5 - angle.cos() * 0.4,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            radius: FloatParam::new(
+                format!("Source {number} Radius"),
+                0.75,
+                FloatRange::Linear {
+                    min: 0.01,
+                    max: 1.5,
+                },
+            ),
+        }
+    }
+
+    fn source(&self) -> VectorSource {
+        VectorSource {
+            x: self.x.value(),
+            y: self.y.value(),
+            radius: self.radius.value(),
+        }
+    }
+}
+
+impl Plugin for VectorFade {
+    // metadata
+    const NAME: &'static str = "Vector Fader";
+    const VENDOR: &'static str = "zoe bat";
+    const URL: &'static str = "https://zoe.kittycat.homes";
+    const EMAIL: &'static str = "zoe@kittycat.homes";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        // stereo
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[
+                new_nonzero_u32(2),
+                new_nonzero_u32(2),
+                new_nonzero_u32(2),
+                new_nonzero_u32(2),
+                new_nonzero_u32(2),
+                new_nonzero_u32(2),
+                new_nonzero_u32(2),
+            ],
+            aux_output_ports: &[],
+
+            names: PortNames {
+                layout: Some("Stereo"),
+                main_input: Some("Source 1"),
+                main_output: Some("Out"),
+                aux_inputs: &[
+                    "Source 2", "Source 3", "Source 4", "Source 5", "Source 6", "Source 7",
+                    "Source 8",
+                ],
+                aux_outputs: &[],
+            },
+        },
+    ];
+
+    type SysExMessage = ();
+
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        _buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.midi.load(&self.params.midi.notes);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.samples_until_update = 0;
+        self.last_inputs = None;
+        self.midi.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let weighting = self.params.weighting.value();
+        let constant_power = self.params.constant_power.value();
+        let sources: [VectorSource; SOURCES] =
+            std::array::from_fn(|index| self.params.sources[index].source());
+
+        self.midi
+            .follow_faders(&[self.params.x_slider.value(), self.params.y_slider.value()]);
+        let midi_settings = self.params.midi.settings(context.transport().sample_rate);
+        let mut next_event = context.next_event();
+        let mut sample_index = 0;
+
+        let mut weights = [0.0; SOURCES];
+        buffer::mix_in_place(buffer, aux, &mut weights, |weights| {
+            self.midi.handle_events(
+                sample_index,
+                &mut next_event,
+                || context.next_event(),
+                &midi_settings,
+            );
+            sample_index += 1;
+
+            // the smoothers and midi glides still have to move every sample
+            let mut cursor = [
+                self.params.x_slider.smoothed.next(),
+                self.params.y_slider.smoothed.next(),
+            ];
+            self.midi.next(&mut cursor);
+
+            if self.samples_until_update == 0 {
+                let inputs = WeightInputs {
+                    cursor,
+                    sources,
+                    weighting,
+                    constant_power,
+                };
+                let [cursor_x, cursor_y] = cursor;
+                let jump = self.last_inputs.is_none();
+                if self.last_inputs != Some(inputs) {
+                    vector::vector_weights(
+                        weighting,
+                        cursor_x,
+                        cursor_y,
+                        &sources,
+                        &mut self.target,
+                    );
+                    if constant_power {
+                        self.target
+                            .iter_mut()
+                            .for_each(|weight| *weight = weight.sqrt());
+                    }
+                    self.last_inputs = Some(inputs);
+                }
+
+                if jump {
+                    self.weights = self.target;
+                }
+                for ((step, weight), target) in self
+                    .weight_steps
+                    .iter_mut()
+                    .zip(&self.weights)
+                    .zip(&self.target)
+                {
+                    *step = (target - weight) / CONTROL_INTERVAL as f32;
+                }
+                self.samples_until_update = CONTROL_INTERVAL;
+            }
+            self.samples_until_update -= 1;
+
+            for (weight, step) in self.weights.iter_mut().zip(&self.weight_steps) {
+                *weight += step;
+            }
+            weights.copy_from_slice(&self.weights);
+        });
+
+        self.midi.publish(&self.params.midi.notes);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for VectorFade {
+    const CLAP_ID: &'static str = "fruitsuite.vector_fader";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Vector crossfading between freely placed sources");
+    const CLAP_MANUAL_URL: Option<&'static str> = None;
+    const CLAP_SUPPORT_URL: Option<&'static str> = Some("https://zoe.kittycat.homes");
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::Stereo,
+        ClapFeature::Utility,
+        ClapFeature::Custom("Crossfade"),
+        ClapFeature::AudioEffect,
+    ];
+}
+
+impl Vst3Plugin for VectorFade {
+    const VST3_CLASS_ID: [u8; 16] = *b"fruit.VecFader00";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
+        Vst3SubCategory::Fx,
+        Vst3SubCategory::Stereo,
+        Vst3SubCategory::Tools,
+    ];
+}
+
+nih_export_clap!(VectorFade);
+nih_export_vst3!(VectorFade);
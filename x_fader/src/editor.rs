@@ -0,0 +1,115 @@
+use atomic_float::AtomicF32;
+use nih_plug::prelude::Editor;
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::widgets::*;
+use nih_plug_vizia::{create_vizia_editor, ViziaState, ViziaTheming};
+use std::sync::{atomic::Ordering, Arc};
+use style::widgets::{Crossfader, CrossfaderExt};
+
+use crate::ConstantPowerCrossfadeParams;
+
+#[derive(Lens)]
+struct Data {
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<AtomicF32>,
+}
+
+impl Model for Data {}
+
+pub(crate) fn default_state() -> Arc<ViziaState> {
+    ViziaState::new(|| (440, 600))
+}
+
+pub(crate) fn create(
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<AtomicF32>,
+    editor_state: Arc<ViziaState>,
+) -> Option<Box<dyn Editor>> {
+    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
+        style::theme::install(cx, &style::color::DARK);
+
+        Data {
+            params: params.clone(),
+            position: position.clone(),
+        }
+        .build(cx);
+
+        VStack::new(cx, |cx| {
+            Label::new(cx, "X Fader").class("title");
+
+            HStack::new(cx, |cx| {
+                VStack::new(cx, |cx| {
+                    // the marker shows where transitions and auto fading moved the fader to
+                    Crossfader::new(
+                        cx,
+                        Data::params,
+                        |params| &params.main_side_mix,
+                        ["Side", "Main"],
+                    )
+                    .live_position(position.clone())
+                    .width(Pixels(180.0))
+                    .height(Pixels(40.0))
+                    .bind(Data::position.map(load), |handle, _| {
+                        handle.cx.needs_redraw()
+                    });
+
+                    Label::new(cx, "Curve").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.curve.curve);
+                    Label::new(cx, "Cut Sharpness");
+                    ParamSlider::new(cx, Data::params, |params| &params.curve.sharpness);
+                    ParamButton::new(cx, Data::params, |params| &params.curve.reverse);
+                    ParamButton::new(cx, Data::params, |params| &params.adaptive);
+
+                    Label::new(cx, "Smoothing").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.smoothing.mode);
+                    ParamSlider::new(cx, Data::params, |params| &params.smoothing.time);
+                    ParamSlider::new(cx, Data::params, |params| &params.smoothing.max_speed);
+
+                    Label::new(cx, "Alignment").class("section");
+                    ParamButton::new(cx, Data::params, |params| &params.align);
+                    ParamButton::new(cx, Data::params, |params| &params.learn);
+                    ParamSlider::new(cx, Data::params, |params| &params.offset);
+                })
+                .row_between(Pixels(style::spacing::SM))
+                .width(Auto)
+                .height(Auto);
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Transition").class("section");
+                    ParamButton::new(cx, Data::params, |params| &params.transition.go);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.quantise);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.length);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.length_unit);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.length_seconds);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.shape);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition.direction);
+
+                    Label::new(cx, "Auto Fade").class("section");
+                    ParamButton::new(cx, Data::params, |params| &params.auto_fade.enabled);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.input);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.detector);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.attack);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.release);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.threshold);
+                    ParamSlider::new(cx, Data::params, |params| &params.auto_fade.range);
+                    ParamButton::new(cx, Data::params, |params| &params.auto_fade.invert);
+                })
+                .row_between(Pixels(style::spacing::SM))
+                .width(Auto)
+                .height(Auto);
+            })
+            .col_between(Pixels(style::spacing::XXL))
+            .width(Auto)
+            .height(Auto);
+        })
+        .row_between(Pixels(style::spacing::SM))
+        .child_top(Pixels(style::spacing::XL))
+        .child_bottom(Pixels(style::spacing::XL))
+        .child_left(Stretch(1.0))
+        .child_right(Stretch(1.0));
+    })
+}
+
+fn load(value: &Arc<AtomicF32>) -> f32 {
+    value.load(Ordering::Relaxed)
+}
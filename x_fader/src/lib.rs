@@ -1,30 +1,182 @@
+use atomic_float::AtomicF32;
+use audio_util::{
+    alignment::{self, DelayEstimator, FractionalDelay},
+    buffer,
+    crossfade::CrossfadeParams,
+    envelope::AutoFadeParams,
+    fader::FaderChain,
+    midi::MidiParams,
+    smoothing::SmoothingParams,
+    transition::TransitionParams,
+};
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use nih_plug_vizia::ViziaState;
+use std::sync::{atomic::Ordering, Arc};
+mod editor;
 
 struct XFade {
     params: Arc<ConstantPowerCrossfadeParams>,
+    chain: FaderChain,
+    sample_rate: f32,
+    estimator: DelayEstimator,
+    /// one delay line per channel, only the input that arrives early gets delayed
+    main_delays: Vec<FractionalDelay>,
+    side_delays: Vec<FractionalDelay>,
+    /// the learn button last buffer, every flip starts a learn pass
+    last_learn: Option<bool>,
+    /// the latency the host was last told about
+    latency: u32,
+    /// where the fader ended up at the end of the last buffer, after transitions and auto fading
+    position: Arc<AtomicF32>,
 }
 
 impl Default for XFade {
     fn default() -> Self {
         Self {
             params: Arc::new(ConstantPowerCrossfadeParams::default()),
+            chain: FaderChain::default(),
+            sample_rate: 44100.0,
+            estimator: DelayEstimator::default(),
+            main_delays: Vec::new(),
+            side_delays: Vec::new(),
+            last_learn: None,
+            latency: 0,
+            position: Arc::new(AtomicF32::new(0.5)),
         }
     }
 }
 
 #[derive(Params)]
 struct ConstantPowerCrossfadeParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<ViziaState>,
+
     #[id = "8eef0a45-7715-4714-845c-1d2dd0bf7c52"]
     pub main_side_mix: FloatParam,
+    #[id = "cb571da2-7336-4133-9759-dafb2fb300ab"]
+    pub adaptive: BoolParam,
+    #[nested(group = "Curve")]
+    pub curve: CrossfadeParams,
+    #[nested(group = "Transition")]
+    pub transition: TransitionParams,
+    #[nested(group = "Auto Fade")]
+    pub auto_fade: AutoFadeParams,
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+    #[nested(group = "Smoothing")]
+    pub smoothing: SmoothingParams,
+
+    #[id = "c0b1a005-d5dd-4ee4-a034-24c618ef6b26"]
+    pub align: BoolParam,
+    /// every flip starts a learn pass, so it works like a button whatever state it is in
+    #[id = "bc669dba-0026-49e4-b0c2-80f1584300f8"]
+    pub learn: BoolParam,
+    /// added on top of the learned delay, positive values delay the main input
+    #[id = "025f851b-e374-4009-9e90-694b9c22baab"]
+    pub offset: FloatParam,
+    /// what the last learn pass measured in milliseconds, positive when the sidechain is late
+    #[persist = "learned-delay"]
+    pub learned_delay: AtomicF32,
 }
 
 impl Default for ConstantPowerCrossfadeParams {
     fn default() -> Self {
         Self {
+            editor_state: editor::default_state(),
             main_side_mix: FloatParam::new("X Mix", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_smoother(SmoothingStyle::Linear(10.0))
                 .with_step_size(0.0001),
+            adaptive: BoolParam::new("Correlation Adaptive", false),
+            curve: CrossfadeParams::default(),
+            transition: TransitionParams::default(),
+            auto_fade: AutoFadeParams::default(),
+            midi: MidiParams::new(&["X"]),
+            smoothing: SmoothingParams::default(),
+            align: BoolParam::new("Time Alignment", false),
+            learn: BoolParam::new("Learn Delay", false),
+            offset: FloatParam::new(
+                "Alignment Offset",
+                0.0,
+                FloatRange::Linear {
+                    min: -alignment::MAX_DELAY_MS,
+                    max: alignment::MAX_DELAY_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.001),
+            learned_delay: AtomicF32::new(0.0),
+        }
+    }
+}
+
+impl XFade {
+    /// how many samples the main input has to be delayed, negative when the sidechain needs it
+    fn alignment_delay(&self) -> f32 {
+        match self.params.align.value() {
+            true => {
+                let delay_ms =
+                    self.params.learned_delay.load(Ordering::Relaxed) + self.params.offset.value();
+                delay_ms / 1000.0 * self.sample_rate
+            }
+            false => 0.0,
+        }
+    }
+
+    /// measures the offset while learning and then lines both inputs up in place
+    fn align(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) {
+        // every click on a toggle button counts, so learning again doesn't need two clicks
+        let learn = self.params.learn.value();
+        if self.last_learn.is_some_and(|last| last != learn) {
+            let samples = alignment::LEARN_TIME_MS / 1000.0 * self.sample_rate;
+            self.estimator.learn(samples as usize);
+        }
+        self.last_learn = Some(learn);
+
+        // learning listens to the inputs before they get delayed
+        if self.estimator.is_learning() {
+            let main = buffer.as_slice_immutable();
+            let side = buffer::sidechain(aux);
+            for sample_index in 0..buffer.samples() {
+                let main_sum: f32 = main.iter().map(|channel| channel[sample_index]).sum();
+                let side_sum: f32 = side.iter().map(|channel| channel[sample_index]).sum();
+                if let Some(delay) = self.estimator.process(main_sum, side_sum) {
+                    self.params
+                        .learned_delay
+                        .store(delay / self.sample_rate * 1000.0, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let delay = self.alignment_delay();
+        for (delays, delay) in [
+            (&mut self.main_delays, delay.max(0.0)),
+            (&mut self.side_delays, (-delay).max(0.0)),
+        ] {
+            delays.iter_mut().for_each(|line| line.set_delay(delay));
+        }
+
+        for (channel, line) in buffer.as_slice().iter_mut().zip(&mut self.main_delays) {
+            channel
+                .iter_mut()
+                .for_each(|sample| *sample = line.process(*sample));
+        }
+        if let Some(sidechain) = aux.inputs.first_mut() {
+            for (channel, line) in sidechain.as_slice().iter_mut().zip(&mut self.side_delays) {
+                channel
+                    .iter_mut()
+                    .for_each(|sample| *sample = line.process(*sample));
+            }
+        }
+
+        // only the main input's delay shows up as latency
+        let latency = delay.max(0.0).round() as u32;
+        if latency != self.latency {
+            self.latency = latency;
+            context.set_latency_samples(latency);
         }
     }
 }
@@ -36,7 +188,7 @@ impl Plugin for XFade {
     const URL: &'static str = "https://zoe.kittycat.homes";
     const EMAIL: &'static str = "zoe@kittycat.homes";
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
@@ -67,54 +219,101 @@ impl Plugin for XFade {
         self.params.clone()
     }
 
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.position.clone(),
+            self.params.editor_state.clone(),
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.chain.initialize(
+            buffer_config.sample_rate,
+            buffer_config.max_buffer_size as usize,
+        );
+
+        self.sample_rate = buffer_config.sample_rate;
+        let max_delay_samples = alignment::MAX_DELAY_MS / 1000.0 * self.sample_rate;
+        self.estimator = DelayEstimator::new(max_delay_samples.ceil() as usize);
+        // the learned and the manual delay can add up
+        let delay_line = FractionalDelay::new((max_delay_samples * 2.0).ceil() as usize);
+        let channels = audio_io_layout
+            .main_input_channels
+            .map(NonZeroU32::get)
+            .unwrap_or_default() as usize;
+        self.main_delays = vec![delay_line.clone(); channels];
+        self.side_delays = vec![delay_line; channels];
+
+        self.latency = self.alignment_delay().max(0.0).round() as u32;
+        context.set_latency_samples(self.latency);
+        self.chain.midi.load(&self.params.midi.notes);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.chain.reset(self.params.main_side_mix.value());
+        self.estimator.cancel();
+        self.main_delays
+            .iter_mut()
+            .chain(self.side_delays.iter_mut())
+            .for_each(FractionalDelay::reset);
+    }
+
     fn process(
         &mut self,
         buffer: &mut Buffer,
         aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let mix_value = self.params.main_side_mix.smoothed.next();
-        let main_audio_mix = main_audio_mix_value(mix_value);
-        let sidechain_mix = sidechain_audio_mix_value(mix_value);
-
-        // apply to main audio input
-        for (sample_index, channel_samples) in buffer.iter_samples().enumerate() {
-            for (channel_index, sample) in channel_samples.into_iter().enumerate() {
-                *sample *= main_audio_mix;
-                *sample += get_sidechain_value_for_main_sample(channel_index, sample_index, aux)
-                    * sidechain_mix;
-            }
-        }
+        self.align(buffer, aux, context);
 
-        ProcessStatus::Normal
-    }
-}
+        let fader = self.params.main_side_mix.value();
+        self.chain.begin_block(
+            fader,
+            self.params.curve.crossfade(),
+            &self.params.transition,
+            &self.params.smoothing,
+            context.transport(),
+        );
+        self.chain.measure(
+            self.params.adaptive.value(),
+            &self.params.auto_fade,
+            buffer.as_slice_immutable(),
+            buffer::sidechain(aux),
+            context.transport().sample_rate,
+        );
 
-/// checks sidechain sample at the position the main sample is
-/// just returns zero when there is an error
-fn get_sidechain_value_for_main_sample(
-    channel_index: usize,
-    sample_index: usize,
-    aux: &mut AuxiliaryBuffers,
-) -> f32 {
-    if let Some(buffer) = aux.inputs.first() {
-        let buffer = buffer.as_slice_immutable();
-        if let Some(sample) = buffer.get(channel_index) {
-            if let Some(channel) = sample.get(sample_index) {
-                return *channel;
-            }
-        }
-    }
+        let midi_settings = self.params.midi.settings(context.transport().sample_rate);
+        let mut next_event = context.next_event();
+        let mut sample_index = 0;
 
-    0.0
-}
+        let mut weights = [0.0; 2];
+        let mut position = self.position.load(Ordering::Relaxed);
+        buffer::mix_in_place(buffer, aux, &mut weights, |weights| {
+            self.chain.midi.handle_events(
+                sample_index,
+                &mut next_event,
+                || context.next_event(),
+                &midi_settings,
+            );
+            sample_index += 1;
 
-fn main_audio_mix_value(mix_value: f32) -> f32 {
-    f32::sqrt(mix_value)
-}
+            let mut mix_value = [fader];
+            self.chain.midi.next(&mut mix_value);
+            position = self.chain.next_weights(mix_value[0], weights);
+        });
+        self.position.store(position, Ordering::Relaxed);
+
+        self.chain.midi.publish(&self.params.midi.notes);
 
-fn sidechain_audio_mix_value(mix_value: f32) -> f32 {
-    f32::sqrt(1.0 - mix_value)
+        ProcessStatus::Normal
+    }
 }
 
 impl ClapPlugin for XFade {
@@ -0,0 +1,257 @@
+use atomic_float::AtomicF32;
+use audio_util::trajectory;
+use nih_plug::prelude::Editor;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use nih_plug_vizia::widgets::*;
+use nih_plug_vizia::{create_vizia_editor, ViziaState, ViziaTheming};
+use std::sync::{atomic::Ordering, Arc};
+use style::widgets::{WidgetColors, XyPad, XyPadExt};
+
+use crate::{ConstantPowerCrossfadeParams, AXES};
+
+/// how far the mouse has to move before drawing adds another point to the path
+const MIN_POINT_DISTANCE: f32 = 0.01;
+/// how many lines the path overlay uses between two drawn points
+const LINES_PER_POINT: usize = 4;
+
+#[derive(Lens)]
+struct Data {
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<[AtomicF32; AXES]>,
+}
+
+impl Model for Data {}
+
+pub(crate) fn default_state() -> Arc<ViziaState> {
+    ViziaState::new(|| (500, 660))
+}
+
+pub(crate) fn create(
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<[AtomicF32; AXES]>,
+    editor_state: Arc<ViziaState>,
+) -> Option<Box<dyn Editor>> {
+    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
+        style::theme::install(cx, &style::color::DARK);
+
+        Data {
+            params: params.clone(),
+            position: position.clone(),
+        }
+        .build(cx);
+
+        VStack::new(cx, |cx| {
+            Label::new(cx, "XY Fader").class("title");
+
+            // two columns keep the window short enough for a 1080p screen
+            HStack::new(cx, |cx| {
+                VStack::new(cx, |cx| {
+                    XyPad::new(
+                        cx,
+                        Data::params,
+                        |params| &params.x_slider,
+                        |params| &params.y_slider,
+                        // the inputs that play in every corner, by their port names
+                        ["[0, 0, 0]", "[0, 1, 0]", "[1, 0, 0]", "[1, 1, 0]"],
+                    )
+                    .live_cursor(position.clone())
+                    .width(Pixels(200.0))
+                    .height(Pixels(200.0))
+                    .redraw_on_move();
+
+                    Label::new(cx, "X");
+                    ParamSlider::new(cx, Data::params, |params| &params.x_slider);
+                    Label::new(cx, "Y");
+                    ParamSlider::new(cx, Data::params, |params| &params.y_slider);
+
+                    Label::new(cx, "Gesture").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.gesture.mode);
+                    ParamSlider::new(cx, Data::params, |params| &params.gesture.bars);
+                    ParamButton::new(cx, Data::params, |params| &params.gesture.clear);
+
+                    Label::new(cx, "Scenes").class("section");
+                    ParamSlider::new(cx, Data::params, |params| &params.scenes.scene);
+                    ParamButton::new(cx, Data::params, |params| &params.scenes.store);
+                    ParamButton::new(cx, Data::params, |params| &params.scenes.recall);
+                    ParamSlider::new(cx, Data::params, |params| &params.scenes.morph_time);
+                })
+                .row_between(Pixels(style::spacing::SM))
+                .width(Auto)
+                .height(Auto);
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Path").class("section");
+                    PathView::new(cx, params.clone(), position.clone())
+                        .width(Pixels(160.0))
+                        .height(Pixels(160.0))
+                        .redraw_on_move();
+                    ParamButton::new(cx, Data::params, |params| &params.path.play);
+                    ParamSlider::new(cx, Data::params, |params| &params.path.shape);
+                    ParamSlider::new(cx, Data::params, |params| &params.path.mode);
+                    ParamButton::new(cx, Data::params, |params| &params.path.sync);
+                    ParamSlider::new(cx, Data::params, |params| &params.path.length_bars);
+                    ParamSlider::new(cx, Data::params, |params| &params.path.length_seconds);
+
+                    Label::new(cx, "Puck").class("section");
+                    ParamButton::new(cx, Data::params, |params| &params.puck.enabled);
+                    ParamSlider::new(cx, Data::params, |params| &params.puck.mass);
+                    ParamSlider::new(cx, Data::params, |params| &params.puck.spring);
+                    ParamSlider::new(cx, Data::params, |params| &params.puck.spring_target);
+                    ParamSlider::new(cx, Data::params, |params| &params.puck.damping);
+                    ParamSlider::new(cx, Data::params, |params| &params.puck.bounce);
+                })
+                .row_between(Pixels(style::spacing::SM))
+                .width(Auto)
+                .height(Auto);
+            })
+            .col_between(Pixels(style::spacing::XXL))
+            .width(Auto)
+            .height(Auto);
+        })
+        .row_between(Pixels(style::spacing::SM))
+        .child_top(Pixels(style::spacing::XL))
+        .child_bottom(Pixels(style::spacing::XL))
+        .child_left(Stretch(1.0))
+        .child_right(Stretch(1.0));
+    })
+}
+
+trait RedrawOnMove {
+    /// the cursors move without anything in the view changing, so this redraws whenever they do
+    fn redraw_on_move(self) -> Self;
+}
+
+impl<V: View> RedrawOnMove for Handle<'_, V> {
+    fn redraw_on_move(self) -> Self {
+        self.bind(
+            Data::position.map(|position| {
+                [
+                    position[0].load(Ordering::Relaxed),
+                    position[1].load(Ordering::Relaxed),
+                ]
+            }),
+            |handle, _| handle.cx.needs_redraw(),
+        )
+    }
+}
+
+/**
+the xy plane with the drawn path and the cursor on top.
+dragging across it draws a new path, y goes up like on the sliders
+ */
+struct PathView {
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<[AtomicF32; AXES]>,
+    drawing: bool,
+}
+
+impl PathView {
+    fn new(
+        cx: &mut Context,
+        params: Arc<ConstantPowerCrossfadeParams>,
+        position: Arc<[AtomicF32; AXES]>,
+    ) -> Handle<Self> {
+        Self {
+            params,
+            position,
+            drawing: false,
+        }
+        .build(cx, |_| {})
+    }
+
+    /// adds the point under the mouse to the path, skipping ones right next to the last
+    fn draw_point(&self, bounds: BoundingBox, x: f32, y: f32) {
+        let point = [
+            ((x - bounds.x) / bounds.w).clamp(0.0, 1.0),
+            (1.0 - (y - bounds.y) / bounds.h).clamp(0.0, 1.0),
+        ];
+        let mut path = self.params.path.path.write();
+        let far_enough = path.points().last().map_or(true, |last| {
+            (point[0] - last[0]).hypot(point[1] - last[1]) >= MIN_POINT_DISTANCE
+        });
+        if far_enough {
+            path.push(point);
+        }
+    }
+}
+
+impl View for PathView {
+    fn element(&self) -> Option<&'static str> {
+        Some("path-view")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                self.drawing = true;
+                cx.capture();
+                self.params.path.path.write().clear();
+                self.draw_point(cx.bounds(), cx.mouse().cursorx, cx.mouse().cursory);
+                cx.needs_redraw();
+                meta.consume();
+            }
+            WindowEvent::MouseMove(x, y) if self.drawing => {
+                self.draw_point(cx.bounds(), *x, *y);
+                cx.needs_redraw();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) if self.drawing => {
+                self.drawing = false;
+                cx.release();
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let colors = WidgetColors::from_style(cx);
+        let to_screen = |point: [f32; 2]| {
+            (
+                bounds.x + point[0] * bounds.w,
+                bounds.y + (1.0 - point[1]) * bounds.h,
+            )
+        };
+
+        let mut frame = vg::Path::new();
+        frame.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.stroke_path(
+            &mut frame,
+            &vg::Paint::color(colors.line).with_line_width(1.0),
+        );
+
+        let shape = self.params.path.shape.value();
+        let path = self.params.path.path.read();
+        let points = path.points();
+        if let Some(first) = points.first() {
+            let mut lengths = Vec::with_capacity(points.len());
+            trajectory::path_lengths(points, &mut lengths);
+
+            let mut line = vg::Path::new();
+            let (x, y) = to_screen(*first);
+            line.move_to(x, y);
+            let lines = points.len() * LINES_PER_POINT;
+            for index in 1..=lines {
+                let amount = index as f32 / lines as f32;
+                let (x, y) = to_screen(trajectory::path_point(points, &lengths, amount, shape));
+                line.line_to(x, y);
+            }
+            canvas.stroke_path(
+                &mut line,
+                &vg::Paint::color(colors.muted).with_line_width(2.0),
+            );
+        }
+        drop(path);
+
+        let (x, y) = to_screen([
+            self.position[0].load(Ordering::Relaxed),
+            self.position[1].load(Ordering::Relaxed),
+        ]);
+        let mut cursor = vg::Path::new();
+        cursor.circle(x, y, 5.0);
+        canvas.fill_path(&mut cursor, &vg::Paint::color(colors.line));
+    }
+}
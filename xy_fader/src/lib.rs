@@ -1,35 +1,105 @@
+use atomic_float::AtomicF32;
+use audio_util::{
+    buffer,
+    crossfade::CrossfadeParams,
+    gesture::{GestureLooper, GestureParams},
+    midi::{MidiControl, MidiParams},
+    multilinear::{self, Normalisation},
+    puck::{Puck, PuckParams},
+    scene::{SceneMorph, SceneParams},
+    smoothing::{AxisSmoother, SmoothingParams},
+    trajectory::{TrajectoryParams, TrajectoryPlayer},
+    transition::Grid,
+};
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use nih_plug_vizia::ViziaState;
+use std::sync::{atomic::Ordering, Arc};
+mod editor;
+
+/// number of fade axes, the plugin mixes `2^AXES` sources
+const AXES: usize = 2;
+/// the corner every input plays, main input first. the aux ports keep the order they
+/// always had, which has the second aux input on the x axis and the first one on y
+const PORT_CORNERS: [usize; multilinear::source_count(AXES)] = [0, 2, 1, 3];
 
 struct XYFade {
     params: Arc<ConstantPowerCrossfadeParams>,
+    midi: MidiControl,
+    smoothers: [AxisSmoother; AXES],
+    scene_morph: SceneMorph,
+    trajectory: TrajectoryPlayer,
+    puck: Puck,
+    gesture: GestureLooper,
+    /// where the axes ended up at the end of the last buffer, for the editor
+    position: Arc<[AtomicF32; AXES]>,
 }
 
 impl Default for XYFade {
     fn default() -> Self {
         Self {
             params: Arc::new(ConstantPowerCrossfadeParams::default()),
+            midi: MidiControl::default(),
+            smoothers: Default::default(),
+            scene_morph: SceneMorph::default(),
+            trajectory: TrajectoryPlayer::default(),
+            puck: Puck::default(),
+            gesture: GestureLooper::default(),
+            position: Arc::new([AtomicF32::new(0.5), AtomicF32::new(0.5)]),
         }
     }
 }
 
 #[derive(Params)]
-struct ConstantPowerCrossfadeParams {
+pub struct ConstantPowerCrossfadeParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<ViziaState>,
+
     #[id = "X"]
     pub x_slider: FloatParam,
     #[id = "Y"]
     pub y_slider: FloatParam,
+    #[id = "normalisation"]
+    pub normalisation: EnumParam<Normalisation>,
+    #[nested(group = "Curve")]
+    pub curve: CrossfadeParams,
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+    #[nested(group = "Smoothing")]
+    pub smoothing: SmoothingParams,
+    #[nested(group = "Scenes")]
+    pub scenes: SceneParams,
+    #[nested(group = "Path")]
+    pub path: TrajectoryParams,
+    #[nested(group = "Puck")]
+    pub puck: PuckParams,
+    #[nested(group = "Gesture")]
+    pub gesture: GestureParams,
 }
 
 impl Default for ConstantPowerCrossfadeParams {
     fn default() -> Self {
         Self {
+            editor_state: editor::default_state(),
             x_slider: FloatParam::new("X", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 }),
             y_slider: FloatParam::new("Y", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            normalisation: EnumParam::new("Normalisation", Normalisation::Off),
+            curve: CrossfadeParams::default(),
+            midi: MidiParams::new(&["X", "Y"]),
+            smoothing: SmoothingParams::default(),
+            scenes: SceneParams::default(),
+            path: TrajectoryParams::default(),
+            puck: PuckParams::default(),
+            gesture: GestureParams::default(),
         }
     }
 }
 
+impl XYFade {
+    fn faders(&self) -> [f32; AXES] {
+        [self.params.x_slider.value(), self.params.y_slider.value()]
+    }
+}
+
 impl Plugin for XYFade {
     // metadata
     const NAME: &'static str = "XY Fader";
@@ -37,7 +107,7 @@ impl Plugin for XYFade {
     const URL: &'static str = "https://zoe.kittycat.homes";
     const EMAIL: &'static str = "zoe@kittycat.homes";
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
@@ -77,68 +147,140 @@ impl Plugin for XYFade {
         self.params.clone()
     }
 
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.position.clone(),
+            self.params.editor_state.clone(),
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        _buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.gesture.load(&self.params.gesture.gesture);
+        self.midi.load(&self.params.midi.notes);
+        self.scene_morph.load(&self.params.scenes.scenes);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.midi.reset();
+        self.scene_morph.reset();
+        self.trajectory.reset();
+        let faders = self.faders();
+        self.puck.reset(faders);
+        for (smoother, fader) in self.smoothers.iter_mut().zip(faders) {
+            smoother.reset(fader);
+        }
+    }
+
     fn process(
         &mut self,
         buffer: &mut Buffer,
         aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let x_mix_value = self.params.x_slider.smoothed.next();
-        let x_low_volume = slider_low_audio_mix_value(x_mix_value);
-        let x_high_volume = slider_high_audio_mix_value(x_low_volume);
-
-        let y_mix_value = self.params.y_slider.smoothed.next();
-        let y_low_volume = slider_low_audio_mix_value(y_mix_value);
-        let y_high_volume = slider_high_audio_mix_value(y_mix_value);
-
-        // apply to main audio input
-        for (sample_index, channel_samples) in buffer.iter_samples().enumerate() {
-            for (channel_index, sample) in channel_samples.into_iter().enumerate() {
-                let sample_0_0 = *sample;
-                let sample_1_0 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 1);
-                let sample_0_1 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 0);
-                let sample_1_1 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 2);
-
-                let low_y = (sample_0_0 * x_low_volume) + (sample_1_0 * x_high_volume);
-                let high_y = (sample_0_1 * x_low_volume) + (sample_1_1 * x_high_volume);
-
-                *sample = (low_y * y_low_volume) + (high_y * y_high_volume);
-            }
+        let crossfade = self.params.curve.crossfade();
+        let normalisation = self.params.normalisation.value();
+        let faders = self.faders();
+        self.midi.follow_faders(&faders);
+        let smoothing = self.params.smoothing.settings();
+        for smoother in &mut self.smoothers {
+            smoother.set_settings(&smoothing, context.transport().sample_rate);
         }
+        self.scene_morph.update(
+            &self.params.scenes,
+            &crossfade,
+            &mut self.midi,
+            context.transport().sample_rate,
+        );
+        let scene_samples =
+            self.params.scenes.morph_time.value() / 1000.0 * context.transport().sample_rate;
+        let grid = Grid::from_transport(context.transport());
+        let path_settings = self.params.path.settings();
+        self.trajectory.sync_path(&self.params.path.path);
+        self.trajectory.begin_block(
+            self.params.path.play.value(),
+            &path_settings,
+            &grid,
+            context.transport().sample_rate,
+        );
+        self.gesture.update(
+            &self.params.gesture.settings(),
+            self.params.gesture.clear.value(),
+            &grid,
+            context.transport().sample_rate,
+        );
+        let puck = self.params.puck.enabled.value();
+        self.puck.set_settings(
+            &self.params.puck.settings(),
+            context.transport().sample_rate,
+        );
+        let midi_settings = self.params.midi.settings(context.transport().sample_rate);
+        let mut next_event = context.next_event();
+        let mut sample_index = 0;
 
-        ProcessStatus::Normal
-    }
-}
+        let mut weights = [0.0; multilinear::source_count(AXES)];
+        let mut corners = weights;
+        let mut last_positions = None;
+        buffer::mix_in_place(buffer, aux, &mut weights, |weights| {
+            self.midi.handle_events(
+                sample_index,
+                &mut next_event,
+                || context.next_event(),
+                &midi_settings,
+            );
+            sample_index += 1;
+            if let Some(program) = self.midi.take_program() {
+                self.scene_morph.recall(
+                    program as usize,
+                    scene_samples,
+                    &crossfade,
+                    &mut self.midi,
+                );
+            }
 
-/// checks sidechain sample at the position the main sample is
-/// just returns zero when there is an error
-fn get_sidechain_value_for_main_sample(
-    channel_index: usize,
-    sample_index: usize,
-    aux: &mut AuxiliaryBuffers,
-    aux_in_selection: usize,
-) -> f32 {
-    if let Some(buffer) = aux.inputs.get(aux_in_selection) {
-        let buffer = buffer.as_slice_immutable();
-        if let Some(sample) = buffer.get(channel_index) {
-            if let Some(channel) = sample.get(sample_index) {
-                return *channel;
+            let mut positions = faders;
+            self.midi.next(&mut positions);
+            self.gesture.next(&mut positions);
+            // a playing path takes over from the faders
+            if let Some(point) = self.trajectory.next_point(&path_settings) {
+                positions = point;
+            }
+            // the puck gets pushed around by everything above
+            positions = match puck {
+                true => self.puck.process(positions),
+                false => {
+                    self.puck.reset(positions);
+                    positions
+                }
+            };
+            for (position, smoother) in positions.iter_mut().zip(&mut self.smoothers) {
+                *position = smoother.process(*position);
+            }
+            let (from, to, amount) = self.scene_morph.next_curves(&crossfade);
+            multilinear::morphed_corner_weights(&positions, &from, &to, amount, &mut corners);
+            multilinear::normalise(&mut corners, normalisation);
+            for (weight, corner) in weights.iter_mut().zip(PORT_CORNERS) {
+                *weight = corners[corner];
+            }
+            last_positions = Some(positions);
+        });
+        if let Some(positions) = last_positions {
+            for (stored, position) in self.position.iter().zip(positions) {
+                stored.store(position, Ordering::Relaxed);
             }
         }
-    }
-
-    0.0
-}
+        self.gesture.publish(&self.params.gesture.gesture);
+        self.midi.publish(&self.params.midi.notes);
+        self.scene_morph.publish(&self.params.scenes.scenes);
 
-fn slider_low_audio_mix_value(mix_value: f32) -> f32 {
-    f32::sqrt(mix_value)
-}
-
-fn slider_high_audio_mix_value(mix_value: f32) -> f32 {
-    f32::sqrt(1.0 - mix_value)
+        ProcessStatus::Normal
+    }
 }
 
 impl ClapPlugin for XYFade {
@@ -164,4 +306,4 @@ impl Vst3Plugin for XYFade {
 }
 
 nih_export_clap!(XYFade);
-nih_export_vst3!(XYFade);
\ No newline at end of file
+nih_export_vst3!(XYFade);
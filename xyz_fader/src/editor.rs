@@ -0,0 +1,100 @@
+use atomic_float::AtomicF32;
+use nih_plug::prelude::Editor;
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::widgets::*;
+use nih_plug_vizia::{create_vizia_editor, ViziaState, ViziaTheming};
+use std::sync::{atomic::Ordering, Arc};
+use style::widgets::{XyzCube, XyzCubeExt, CORNERS};
+
+use crate::{ConstantPowerCrossfadeParams, AXES};
+
+#[derive(Lens)]
+struct Data {
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<[AtomicF32; AXES]>,
+    weights: Arc<[AtomicF32; CORNERS]>,
+}
+
+impl Model for Data {}
+
+pub(crate) fn default_state() -> Arc<ViziaState> {
+    ViziaState::new(|| (420, 830))
+}
+
+pub(crate) fn create(
+    params: Arc<ConstantPowerCrossfadeParams>,
+    position: Arc<[AtomicF32; AXES]>,
+    weights: Arc<[AtomicF32; CORNERS]>,
+    editor_state: Arc<ViziaState>,
+) -> Option<Box<dyn Editor>> {
+    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
+        style::theme::install(cx, &style::color::DARK);
+
+        Data {
+            params: params.clone(),
+            position: position.clone(),
+            weights: weights.clone(),
+        }
+        .build(cx);
+
+        VStack::new(cx, |cx| {
+            Label::new(cx, "XYZ Fader").class("title");
+
+            HStack::new(cx, |cx| {
+                XyzCube::new(
+                    cx,
+                    Data::params,
+                    |params| &params.x_slider,
+                    |params| &params.y_slider,
+                    |params| &params.z_slider,
+                )
+                .live_cursor(position.clone())
+                .weights(weights.clone())
+                .width(Pixels(280.0))
+                .height(Pixels(280.0))
+                // the point and the weights move without anything in the view changing
+                .bind(
+                    Data::weights.map(|weights| -> [f32; CORNERS] {
+                        std::array::from_fn(|corner| weights[corner].load(Ordering::Relaxed))
+                    }),
+                    |handle, _| handle.cx.needs_redraw(),
+                );
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Z");
+                    ParamSlider::new(cx, Data::params, |params| &params.z_slider)
+                        .width(Pixels(100.0));
+                })
+                .child_top(Stretch(1.0))
+                .child_bottom(Stretch(1.0));
+            })
+            .height(Auto);
+
+            Label::new(cx, "X");
+            ParamSlider::new(cx, Data::params, |params| &params.x_slider);
+            Label::new(cx, "Y");
+            ParamSlider::new(cx, Data::params, |params| &params.y_slider);
+            ParamSlider::new(cx, Data::params, |params| &params.normalisation);
+
+            Label::new(cx, "Wander").class("section");
+            ParamButton::new(cx, Data::params, |params| &params.wander.enabled);
+            ParamSlider::new(cx, Data::params, |params| &params.wander.mode);
+            ParamSlider::new(cx, Data::params, |params| &params.wander.seed);
+            ParamSlider::new(cx, Data::params, |params| &params.wander.rate);
+            ParamSlider::new(cx, Data::params, |params| &params.wander.step);
+            ParamSlider::new(cx, Data::params, |params| &params.wander.bounds);
+            ParamSlider::new(cx, Data::params, |params| &params.wander.attraction);
+
+            Label::new(cx, "Scenes").class("section");
+            ParamSlider::new(cx, Data::params, |params| &params.scenes.scene);
+            ParamButton::new(cx, Data::params, |params| &params.scenes.store);
+            ParamButton::new(cx, Data::params, |params| &params.scenes.recall);
+            ParamSlider::new(cx, Data::params, |params| &params.scenes.morph_time);
+        })
+        .row_between(Pixels(style::spacing::SM))
+        .child_top(Pixels(style::spacing::XL))
+        .child_bottom(Pixels(style::spacing::XL))
+        .child_left(Stretch(1.0))
+        .child_right(Stretch(1.0));
+    })
+}
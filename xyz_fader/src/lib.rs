@@ -1,41 +1,113 @@
+use atomic_float::AtomicF32;
+use audio_util::{
+    buffer,
+    crossfade::CrossfadeParams,
+    lfo::{Lfo, LfoParams},
+    midi::{MidiControl, MidiParams},
+    multilinear::{self, Normalisation},
+    scene::{SceneMorph, SceneParams},
+    smoothing::{AxisSmoother, SmoothingParams},
+    transition::Grid,
+    wander::{Wander, WanderParams},
+};
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use nih_plug_vizia::ViziaState;
+use std::sync::{atomic::Ordering, Arc};
+mod editor;
+
+/// number of fade axes, the plugin mixes `2^AXES` sources
+const AXES: usize = 3;
 
 struct XYZFade {
     params: Arc<ConstantPowerCrossfadeParams>,
+    /// one per axis, seeded differently so the random shapes don't move together
+    lfos: [Lfo; AXES],
+    midi: MidiControl,
+    smoothers: [AxisSmoother; AXES],
+    scene_morph: SceneMorph,
+    wander: Wander,
+    /// where the axes ended up at the end of the last buffer, for the editor
+    position: Arc<[AtomicF32; AXES]>,
+    /// how much of every source played at the end of the last buffer
+    weights: Arc<[AtomicF32; multilinear::source_count(AXES)]>,
 }
 
 impl Default for XYZFade {
     fn default() -> Self {
         Self {
             params: Arc::new(ConstantPowerCrossfadeParams::default()),
+            lfos: std::array::from_fn(|axis| Lfo::new(axis as u32 + 1)),
+            midi: MidiControl::default(),
+            smoothers: Default::default(),
+            scene_morph: SceneMorph::default(),
+            wander: Wander::default(),
+            position: Arc::new(std::array::from_fn(|_| AtomicF32::new(0.5))),
+            weights: Arc::new(std::array::from_fn(|_| AtomicF32::new(0.0))),
         }
     }
 }
 
 #[derive(Params)]
-struct ConstantPowerCrossfadeParams {
+pub struct ConstantPowerCrossfadeParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<ViziaState>,
+
     #[id = "X"]
     pub x_slider: FloatParam,
     #[id = "Y"]
     pub y_slider: FloatParam,
     #[id = "Z"]
     pub z_slider: FloatParam,
+    #[id = "normalisation"]
+    pub normalisation: EnumParam<Normalisation>,
+    #[nested(group = "Curve")]
+    pub curve: CrossfadeParams,
+    #[nested(id_prefix = "x-lfo", group = "X LFO")]
+    pub x_lfo: LfoParams,
+    #[nested(id_prefix = "y-lfo", group = "Y LFO")]
+    pub y_lfo: LfoParams,
+    #[nested(id_prefix = "z-lfo", group = "Z LFO")]
+    pub z_lfo: LfoParams,
+    #[nested(group = "MIDI")]
+    pub midi: MidiParams,
+    #[nested(group = "Smoothing")]
+    pub smoothing: SmoothingParams,
+    #[nested(group = "Scenes")]
+    pub scenes: SceneParams,
+    #[nested(group = "Wander")]
+    pub wander: WanderParams,
 }
 
 impl Default for ConstantPowerCrossfadeParams {
     fn default() -> Self {
         Self {
-            x_slider: FloatParam::new("X", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_smoother(SmoothingStyle::Linear(10.0)),
-            y_slider: FloatParam::new("Y", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_smoother(SmoothingStyle::Linear(10.0)),
-            z_slider: FloatParam::new("Z", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_smoother(SmoothingStyle::Linear(10.0)),
+            editor_state: editor::default_state(),
+            x_slider: FloatParam::new("X", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            y_slider: FloatParam::new("Y", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            z_slider: FloatParam::new("Z", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            normalisation: EnumParam::new("Normalisation", Normalisation::Off),
+            curve: CrossfadeParams::default(),
+            x_lfo: LfoParams::named("X "),
+            y_lfo: LfoParams::named("Y "),
+            z_lfo: LfoParams::named("Z "),
+            midi: MidiParams::new(&["X", "Y", "Z"]),
+            smoothing: SmoothingParams::default(),
+            scenes: SceneParams::default(),
+            wander: WanderParams::default(),
         }
     }
 }
 
+impl XYZFade {
+    fn faders(&self) -> [f32; AXES] {
+        [
+            self.params.x_slider.value(),
+            self.params.y_slider.value(),
+            self.params.z_slider.value(),
+        ]
+    }
+}
+
 impl Plugin for XYZFade {
     // metadata
     const NAME: &'static str = "XYZ Fader";
@@ -43,7 +115,7 @@ impl Plugin for XYZFade {
     const URL: &'static str = "https://zoe.kittycat.homes";
     const EMAIL: &'static str = "zoe@kittycat.homes";
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
@@ -68,6 +140,7 @@ impl Plugin for XYZFade {
                 layout: Some("Stereo"),
                 main_input: Some("[0, 0, 0]"),
                 main_output: Some("Out"),
+                // these have always listed x, z and y, the ports play the corners in source order
                 aux_inputs: &[
                     "[1, 0, 0]",
                     "[0, 0, 1]",
@@ -90,87 +163,128 @@ impl Plugin for XYZFade {
         self.params.clone()
     }
 
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.position.clone(),
+            self.weights.clone(),
+            self.params.editor_state.clone(),
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        _buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.midi.load(&self.params.midi.notes);
+        self.scene_morph.load(&self.params.scenes.scenes);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.lfos.iter_mut().for_each(Lfo::reset);
+        self.midi.reset();
+        self.scene_morph.reset();
+        self.wander.reset();
+        let faders = self.faders();
+        for (smoother, fader) in self.smoothers.iter_mut().zip(faders) {
+            smoother.reset(fader);
+        }
+    }
+
     fn process(
         &mut self,
         buffer: &mut Buffer,
         aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let x_mix_value = self.params.x_slider.smoothed.next();
-        let x_low_volume = slider_low_audio_mix_value(x_mix_value);
-        let x_high_volume = slider_high_audio_mix_value(x_low_volume);
-
-        let y_mix_value = self.params.y_slider.smoothed.next();
-        let y_low_volume = slider_low_audio_mix_value(y_mix_value);
-        let y_high_volume = slider_high_audio_mix_value(y_mix_value);
-
-        let z_mix_value = self.params.z_slider.smoothed.next();
-        let z_low_volume = slider_low_audio_mix_value(z_mix_value);
-        let z_high_value = slider_high_audio_mix_value(z_mix_value);
-
-        // apply to main audio input
-        for (sample_index, channel_samples) in buffer.iter_samples().enumerate() {
-            for (channel_index, sample) in channel_samples.into_iter().enumerate() {
-                let sample_0_0_0 = *sample;
-                let sample_1_0_0 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 0);
-                let mix_x_1 = (sample_0_0_0 * x_low_volume) + (sample_1_0_0 * x_high_volume);
-
-                let sample_0_0_1 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 1);
-                let sample_1_0_1 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 2);
-                let mix_x_2 = (sample_0_0_1 * x_low_volume) + (sample_1_0_1 * x_high_volume);
-
-                let sample_0_1_0 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 3);
-                let sample_1_1_0 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 4);
-                let mix_x_3 = (sample_0_1_0 * x_low_volume) + (sample_1_1_0 * x_high_volume);
-
-                let sample_0_1_1 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 5);
-                let sample_1_1_1 =
-                    get_sidechain_value_for_main_sample(channel_index, sample_index, aux, 6);
-                let mix_x_4 = (sample_0_1_1 * x_low_volume) + (sample_1_1_1 * x_high_volume);
-
-                let mix_y_1 = (mix_x_1 * y_low_volume) + (mix_x_2 * y_high_volume);
-                let mix_y_2 = (mix_x_3 * y_low_volume) + (mix_x_4 * y_high_volume);
-
-                *sample = (mix_y_1 * z_low_volume) + (mix_y_2 * z_high_value);
-            }
+        let crossfade = self.params.curve.crossfade();
+        let normalisation = self.params.normalisation.value();
+        let lfo_settings = [
+            self.params.x_lfo.settings(),
+            self.params.y_lfo.settings(),
+            self.params.z_lfo.settings(),
+        ];
+        let grid = Grid::from_transport(context.transport());
+        let sample_rate = context.transport().sample_rate;
+        for (lfo, settings) in self.lfos.iter_mut().zip(&lfo_settings) {
+            lfo.begin_block(settings, &grid, sample_rate);
         }
 
-        ProcessStatus::Normal
-    }
-}
+        let faders = self.faders();
+        self.midi.follow_faders(&faders);
+        let smoothing = self.params.smoothing.settings();
+        for smoother in &mut self.smoothers {
+            smoother.set_settings(&smoothing, sample_rate);
+        }
+        self.scene_morph
+            .update(&self.params.scenes, &crossfade, &mut self.midi, sample_rate);
+        let scene_samples = self.params.scenes.morph_time.value() / 1000.0 * sample_rate;
+        let wander = self.params.wander.enabled.value();
+        self.wander
+            .set_settings(&self.params.wander.settings(), sample_rate);
+        if !wander {
+            // switching it on always starts the same way
+            self.wander.reset();
+        }
+        let midi_settings = self.params.midi.settings(sample_rate);
+        let mut next_event = context.next_event();
+        let mut sample_index = 0;
 
-/// checks sidechain sample at the position the main sample is
-/// just returns zero when there is an error
-fn get_sidechain_value_for_main_sample(
-    channel_index: usize,
-    sample_index: usize,
-    aux: &mut AuxiliaryBuffers,
-    aux_in_selection: usize,
-) -> f32 {
-    if let Some(buffer) = aux.inputs.get(aux_in_selection) {
-        let buffer = buffer.as_slice_immutable();
-        if let Some(sample) = buffer.get(channel_index) {
-            if let Some(channel) = sample.get(sample_index) {
-                return *channel;
+        let mut weights = [0.0; multilinear::source_count(AXES)];
+        let mut last_positions = None;
+        buffer::mix_in_place(buffer, aux, &mut weights, |weights| {
+            self.midi.handle_events(
+                sample_index,
+                &mut next_event,
+                || context.next_event(),
+                &midi_settings,
+            );
+            sample_index += 1;
+            if let Some(program) = self.midi.take_program() {
+                self.scene_morph.recall(
+                    program as usize,
+                    scene_samples,
+                    &crossfade,
+                    &mut self.midi,
+                );
             }
-        }
-    }
 
-    0.0
-}
+            // midi moves the center the lfos swing around, just like the sliders
+            let mut positions = faders;
+            self.midi.next(&mut positions);
+            for (((position, smoother), lfo), settings) in positions
+                .iter_mut()
+                .zip(&mut self.smoothers)
+                .zip(&mut self.lfos)
+                .zip(&lfo_settings)
+            {
+                *position = lfo.next(smoother.process(*position), settings);
+            }
+            if wander {
+                self.wander.next(&mut positions);
+            }
+            let (from, to, amount) = self.scene_morph.next_curves(&crossfade);
+            multilinear::morphed_corner_weights(&positions, &from, &to, amount, weights);
+            multilinear::normalise(weights, normalisation);
+            last_positions = Some(positions);
+        });
+        if let Some(positions) = last_positions {
+            for (stored, position) in self.position.iter().zip(positions) {
+                stored.store(position, Ordering::Relaxed);
+            }
+            for (stored, weight) in self.weights.iter().zip(weights) {
+                stored.store(weight, Ordering::Relaxed);
+            }
+        }
 
-fn slider_low_audio_mix_value(mix_value: f32) -> f32 {
-    f32::sqrt(mix_value)
-}
+        self.midi.publish(&self.params.midi.notes);
+        self.scene_morph.publish(&self.params.scenes.scenes);
 
-fn slider_high_audio_mix_value(mix_value: f32) -> f32 {
-    f32::sqrt(1.0 - mix_value)
+        ProcessStatus::Normal
+    }
 }
 
 impl ClapPlugin for XYZFade {